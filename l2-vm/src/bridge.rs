@@ -1,8 +1,9 @@
 // NUSA Chain Universal Cross-Chain Bridge
 // Supports: Ethereum, Bitcoin, Solana, Polygon, BSC, Avalanche
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Serialize, Deserialize};
+use sha2::{Sha256, Digest};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Chain {
@@ -38,8 +39,131 @@ pub enum BridgeStatus {
     Failed,
 }
 
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    let out = hasher.finalize();
+    let mut result = [0u8; 32];
+    result.copy_from_slice(&out);
+    result
+}
+
+fn hash_lock_leaf(tx_id: &str, amount: u64, from_chain: &Chain) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(tx_id.as_bytes());
+    hasher.update(&amount.to_le_bytes());
+    hasher.update(format!("{:?}", from_chain).as_bytes());
+    let out = hasher.finalize();
+    let mut result = [0u8; 32];
+    result.copy_from_slice(&out);
+    result
+}
+
+/// Which side of the hashed pair a sibling sat on, so a verifier can
+/// recompute the parent in the right order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sibling {
+    Left([u8; 32]),
+    Right([u8; 32]),
+}
+
+/// Inclusion proof for one leaf: its position plus the sibling hash at
+/// every level up to the root.
+#[derive(Debug, Clone)]
+pub struct MerkleProof {
+    pub leaf_index: usize,
+    pub siblings: Vec<Sibling>,
+}
+
+/// Append-only Merkle commitment over the locked-asset set. Leaves are
+/// `hash(tx_id || amount || from_chain)`; `lock_assets` appends a leaf and
+/// the root is rebuilt from all leaves whenever it is read, so a relayer can carry a succinct inclusion proof
+/// across chains instead of a destination chain trusting the source
+/// chain's local map directly. Insertion-only - unlocks are tracked in a
+/// separate nullifier set rather than mutating the tree.
+struct LockCommitment {
+    leaves: Vec<[u8; 32]>,
+}
+
+impl LockCommitment {
+    fn new() -> Self {
+        LockCommitment { leaves: Vec::new() }
+    }
+
+    fn push(&mut self, leaf: [u8; 32]) -> usize {
+        self.leaves.push(leaf);
+        self.leaves.len() - 1
+    }
+
+    fn levels(&self) -> Vec<Vec<[u8; 32]>> {
+        if self.leaves.is_empty() {
+            return vec![vec![[0u8; 32]]];
+        }
+
+        let mut levels = vec![self.leaves.clone()];
+        while levels.last().unwrap().len() > 1 {
+            let current = levels.last().unwrap();
+            let mut next = Vec::with_capacity((current.len() + 1) / 2);
+            for pair in current.chunks(2) {
+                let left = pair[0];
+                let right = if pair.len() == 2 { pair[1] } else { pair[0] };
+                next.push(hash_pair(&left, &right));
+            }
+            levels.push(next);
+        }
+        levels
+    }
+
+    fn root(&self) -> [u8; 32] {
+        *self.levels().last().unwrap().first().unwrap()
+    }
+
+    fn proof(&self, mut index: usize) -> Option<MerkleProof> {
+        if index >= self.leaves.len() {
+            return None;
+        }
+        let leaf_index = index;
+        let levels = self.levels();
+        let mut siblings = Vec::new();
+
+        for level in &levels[..levels.len() - 1] {
+            let pair_index = index ^ 1;
+            let sibling_hash = if pair_index < level.len() { level[pair_index] } else { level[index] };
+
+            if index % 2 == 0 {
+                siblings.push(Sibling::Right(sibling_hash));
+            } else {
+                siblings.push(Sibling::Left(sibling_hash));
+            }
+
+            index /= 2;
+        }
+
+        Some(MerkleProof { leaf_index, siblings })
+    }
+}
+
+/// Recompute the root from `leaf` and `proof.siblings`, and check it
+/// matches `root`. This is the destination-chain side of `prove_lock`: no
+/// access to the source chain's `locked_assets` map is required.
+pub fn verify_lock_proof(root: &[u8; 32], proof: &MerkleProof, leaf: &[u8; 32]) -> bool {
+    let mut hash = *leaf;
+    for sibling in &proof.siblings {
+        hash = match sibling {
+            Sibling::Left(h) => hash_pair(h, &hash),
+            Sibling::Right(h) => hash_pair(&hash, h),
+        };
+    }
+    &hash == root
+}
+
 pub struct UniversalBridge {
     locked_assets: HashMap<String, u64>,
+    lock_commitment: LockCommitment,
+    lock_leaf_index: HashMap<String, usize>,
+    lock_meta: HashMap<String, (u64, Chain)>,
+    spent_nullifiers: HashSet<String>,
     validators: Vec<String>,
     min_validators: usize,
 }
@@ -48,6 +172,10 @@ impl UniversalBridge {
     pub fn new() -> Self {
         Self {
             locked_assets: HashMap::new(),
+            lock_commitment: LockCommitment::new(),
+            lock_leaf_index: HashMap::new(),
+            lock_meta: HashMap::new(),
+            spent_nullifiers: HashSet::new(),
             validators: vec![
                 "validator1".to_string(),
                 "validator2".to_string(),
@@ -65,47 +193,89 @@ impl UniversalBridge {
         amount: u64,
     ) -> Result<(), String> {
         println!("🔒 Locking {} tokens on {:?}", amount, chain);
-        
+
         // Verify sufficient balance
         // (Production: Check actual chain balance)
-        
+
         self.locked_assets.insert(tx_id.to_string(), amount);
-        
+
+        let leaf = hash_lock_leaf(tx_id, amount, &chain);
+        let index = self.lock_commitment.push(leaf);
+        self.lock_leaf_index.insert(tx_id.to_string(), index);
+        self.lock_meta.insert(tx_id.to_string(), (amount, chain));
+
         Ok(())
     }
 
-    // Mint wrapped assets on destination chain
+    /// Current root of the locked-asset commitment tree.
+    pub fn lock_root(&self) -> [u8; 32] {
+        self.lock_commitment.root()
+    }
+
+    /// Inclusion proof a relayer can carry to the destination chain to
+    /// substantiate a lock without it trusting this node's map directly.
+    pub fn prove_lock(&self, tx_id: &str) -> Option<MerkleProof> {
+        let index = *self.lock_leaf_index.get(tx_id)?;
+        self.lock_commitment.proof(index)
+    }
+
+    /// The leaf hash for `tx_id`, for a relayer to pair with `prove_lock`'s
+    /// proof - avoids recomputing it from first principles on the source
+    /// side.
+    pub fn lock_leaf(&self, tx_id: &str) -> Option<[u8; 32]> {
+        let (amount, chain) = self.lock_meta.get(tx_id)?;
+        Some(hash_lock_leaf(tx_id, *amount, chain))
+    }
+
+    // Mint wrapped assets on destination chain. Verifies a Merkle
+    // inclusion proof against the committed lock-set root instead of
+    // consulting `locked_assets` directly, since the destination chain
+    // cannot see the source chain's local map.
     pub fn mint_wrapped(
         &self,
         tx_id: &str,
         to_chain: Chain,
         to_address: &str,
         amount: u64,
+        from_chain: Chain,
+        root: [u8; 32],
+        proof: &MerkleProof,
     ) -> Result<String, String> {
         println! ("🪙 Minting {} wrapped tokens on {:?}", amount, to_chain);
-        
-        // Verify lock exists
-        if !self.locked_assets.contains_key(tx_id) {
-            return Err("No locked assets found".to_string());
+
+        if self.spent_nullifiers.contains(tx_id) {
+            return Err("lock has already been spent".to_string());
         }
-        
+
+        let leaf = hash_lock_leaf(tx_id, amount, &from_chain);
+        if !verify_lock_proof(&root, proof, &leaf) {
+            return Err("invalid lock inclusion proof".to_string());
+        }
+
         // Generate wrapped token ID
         let wrapped_token_id = format!("w{:? }-{}", to_chain, tx_id);
-        
+
         Ok(wrapped_token_id)
     }
 
-    // Burn wrapped assets and unlock original
+    // Burn wrapped assets and unlock original. The commitment tree is
+    // insertion-only, so a spent lock is tracked in a nullifier set rather
+    // than removed from the tree.
     pub fn burn_and_unlock(
         &mut self,
         tx_id: &str,
         amount: u64,
     ) -> Result<(), String> {
         println! ("🔥 Burning wrapped tokens and unlocking original");
-        
+
+        if self.spent_nullifiers.contains(tx_id) {
+            return Err("lock has already been spent".to_string());
+        }
+
         // Remove from locked assets
         self.locked_assets.remove(tx_id);
-        
+        self.spent_nullifiers.insert(tx_id.to_string());
+
         Ok(())
     }
 
@@ -185,13 +355,18 @@ mod tests {
     #[test]
     fn test_bridge_eth_to_nusa() {
         let mut bridge = UniversalBridge::new();
-        
+
         // Lock ETH
         bridge.lock_assets("tx123", Chain::Ethereum, 1000). unwrap();
-        
+
+        let root = bridge.lock_root();
+        let proof = bridge.prove_lock("tx123").unwrap();
+
         // Mint wrapped on NUSA
-        let wrapped = bridge.mint_wrapped("tx123", Chain::NUSA, "nusa1abc", 1000).unwrap();
-        
+        let wrapped = bridge
+            .mint_wrapped("tx123", Chain::NUSA, "nusa1abc", 1000, Chain::Ethereum, root, &proof)
+            .unwrap();
+
         assert!(wrapped.contains("wNUSA"));
     }
 
@@ -199,7 +374,53 @@ mod tests {
     fn test_calculate_fee() {
         let bridge = UniversalBridge::new();
         let fee = bridge.calculate_fee(10000, Chain::Ethereum, Chain::NUSA);
-        
+
         assert!(fee > 0);
     }
+
+    #[test]
+    fn proof_verifies_for_every_lock_in_a_growing_tree() {
+        let mut bridge = UniversalBridge::new();
+        let tx_ids = ["tx-a", "tx-b", "tx-c", "tx-d", "tx-e"];
+
+        for (i, tx_id) in tx_ids.iter().enumerate() {
+            bridge.lock_assets(tx_id, Chain::Ethereum, 100 * (i as u64 + 1)).unwrap();
+        }
+
+        let root = bridge.lock_root();
+        for tx_id in tx_ids {
+            let proof = bridge.prove_lock(tx_id).unwrap();
+            let leaf = bridge.lock_leaf(tx_id).unwrap();
+            assert!(verify_lock_proof(&root, &proof, &leaf));
+        }
+    }
+
+    #[test]
+    fn mint_wrapped_rejects_invalid_proof() {
+        let mut bridge = UniversalBridge::new();
+        bridge.lock_assets("tx123", Chain::Ethereum, 1000).unwrap();
+        let proof = bridge.prove_lock("tx123").unwrap();
+        let root = bridge.lock_root();
+
+        // Tamper with the amount so the recomputed leaf no longer matches
+        // what was actually locked.
+        let result = bridge.mint_wrapped("tx123", Chain::NUSA, "nusa1abc", 999, Chain::Ethereum, root, &proof);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn mint_wrapped_rejects_replayed_lock() {
+        let mut bridge = UniversalBridge::new();
+        bridge.lock_assets("tx123", Chain::Ethereum, 1000).unwrap();
+        let proof = bridge.prove_lock("tx123").unwrap();
+        let root = bridge.lock_root();
+
+        bridge
+            .mint_wrapped("tx123", Chain::NUSA, "nusa1abc", 1000, Chain::Ethereum, root, &proof)
+            .unwrap();
+        bridge.burn_and_unlock("tx123", 1000).unwrap();
+
+        let result = bridge.mint_wrapped("tx123", Chain::NUSA, "nusa1abc", 1000, Chain::Ethereum, root, &proof);
+        assert!(result.is_err());
+    }
 }
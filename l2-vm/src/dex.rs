@@ -81,7 +81,8 @@ impl LiquidityPool {
 
 pub struct NusaDEX {
     pools: HashMap<String, LiquidityPool>,
-    orders: Vec<LimitOrder>,
+    order_books: HashMap<String, OrderBook>,
+    next_sequence: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -92,22 +93,50 @@ pub struct LimitOrder {
     pub token_out: String,
     pub amount_in: u64,
     pub price: f64,
-    pub filled: bool,
+    /// How much of `amount_in` is still unfilled. Zero means fully filled;
+    /// this replaces a bare filled flag so orders can be partially matched.
+    pub remaining_in: u64,
+    /// Arrival order, used to break price ties by price-time priority.
+    pub sequence: u64,
+}
+
+/// A trading pair's resting limit orders, as two sides. Orders are appended
+/// in arrival order; `match_orders` sorts each side by price-time priority
+/// (best bid/ask first) before crossing.
+#[derive(Default)]
+struct OrderBook {
+    /// Buying the pool's `token_a` by spending `token_b`.
+    bids: Vec<LimitOrder>,
+    /// Selling the pool's `token_a` for `token_b`.
+    asks: Vec<LimitOrder>,
+}
+
+/// A completed trade, whether matched against another resting order or
+/// routed through the pool's AMM as a fallback.
+#[derive(Debug, Clone)]
+pub struct TradeEvent {
+    pub pool_id: String,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub price: f64,
+    pub buyer: String,
+    pub seller: String,
 }
 
 impl NusaDEX {
     pub fn new() -> Self {
         Self {
             pools: HashMap::new(),
-            orders: Vec::new(),
+            order_books: HashMap::new(),
+            next_sequence: 0,
         }
     }
-    
+
     pub fn create_pool(&mut self, token_a: String, token_b: String) {
         let pool_id = format!("{}-{}", token_a, token_b);
         self.pools.insert(pool_id, LiquidityPool::new(token_a, token_b));
     }
-    
+
     pub fn instant_swap(&mut self, pool_id: String, token_in: String, amount: u64) -> u64 {
         if let Some(pool) = self. pools.get_mut(&pool_id) {
             pool.swap(token_in, amount)
@@ -115,4 +144,480 @@ impl NusaDEX {
             0
         }
     }
+
+    /// Place a resting limit order against `pool_id`'s book. `token_in`
+    /// must be one of the pool's two tokens; whether it lands on the bid
+    /// or ask side falls out of which token that is.
+    pub fn place_limit_order(
+        &mut self,
+        pool_id: &str,
+        trader: String,
+        token_in: String,
+        amount_in: u64,
+        price: f64,
+    ) -> Result<String, String> {
+        let (token_a, token_b) = {
+            let pool = self
+                .pools
+                .get(pool_id)
+                .ok_or_else(|| format!("unknown pool {}", pool_id))?;
+            (pool.token_a.clone(), pool.token_b.clone())
+        };
+
+        let token_out = if token_in == token_a {
+            token_b.clone()
+        } else if token_in == token_b {
+            token_a.clone()
+        } else {
+            return Err(format!("{} is not a token of pool {}", token_in, pool_id));
+        };
+
+        self.next_sequence += 1;
+        let order = LimitOrder {
+            id: format!("order-{}", self.next_sequence),
+            trader,
+            token_in: token_in.clone(),
+            token_out,
+            amount_in,
+            price,
+            remaining_in: amount_in,
+            sequence: self.next_sequence,
+        };
+        let order_id = order.id.clone();
+
+        let book = self.order_books.entry(pool_id.to_string()).or_insert_with(OrderBook::default);
+        if token_in == token_a {
+            book.asks.push(order);
+        } else {
+            book.bids.push(order);
+        }
+
+        Ok(order_id)
+    }
+
+    /// Cross resting limit orders in `pool_id`'s book by price-time
+    /// priority: the best bid and best ask trade whenever `bid.price >=
+    /// ask.price`, at the resting ask's price, partially filling when
+    /// sizes differ. When `use_amm_fallback` is set, any orders left
+    /// resting once no more crosses are possible route their remainder
+    /// through the pool's AMM instead of waiting indefinitely for a
+    /// counterparty (hybrid CLOB+AMM).
+    pub fn match_orders(&mut self, pool_id: &str, use_amm_fallback: bool) -> Vec<TradeEvent> {
+        let mut trades = Vec::new();
+
+        if let Some(book) = self.order_books.get_mut(pool_id) {
+            // `total_cmp` rather than `partial_cmp(..).unwrap()` - a resting
+            // order with a NaN price must not be able to panic the whole
+            // matching engine.
+            book.bids
+                .sort_by(|a, b| b.price.total_cmp(&a.price).then(a.sequence.cmp(&b.sequence)));
+            book.asks
+                .sort_by(|a, b| a.price.total_cmp(&b.price).then(a.sequence.cmp(&b.sequence)));
+
+            loop {
+                let crosses = match (book.bids.first(), book.asks.first()) {
+                    (Some(bid), Some(ask)) => bid.price >= ask.price,
+                    _ => false,
+                };
+                if !crosses {
+                    break;
+                }
+
+                let price = book.asks[0].price;
+                let base_from_ask = book.asks[0].remaining_in;
+                let base_from_bid = (book.bids[0].remaining_in as f64 / price).floor() as u64;
+                let trade_base = base_from_ask.min(base_from_bid);
+                if trade_base == 0 {
+                    break;
+                }
+                let trade_quote = (trade_base as f64 * price).round() as u64;
+
+                book.asks[0].remaining_in -= trade_base;
+                book.bids[0].remaining_in -= trade_quote;
+
+                trades.push(TradeEvent {
+                    pool_id: pool_id.to_string(),
+                    base_amount: trade_base,
+                    quote_amount: trade_quote,
+                    price,
+                    buyer: book.bids[0].trader.clone(),
+                    seller: book.asks[0].trader.clone(),
+                });
+
+                book.bids.retain(|o| o.remaining_in > 0);
+                book.asks.retain(|o| o.remaining_in > 0);
+            }
+        }
+
+        if use_amm_fallback {
+            trades.extend(self.route_remainder_to_amm(pool_id));
+        }
+
+        trades
+    }
+
+    /// Fill every order still resting in `pool_id`'s book directly against
+    /// the AMM, at whatever price the pool currently quotes.
+    fn route_remainder_to_amm(&mut self, pool_id: &str) -> Vec<TradeEvent> {
+        let mut trades = Vec::new();
+
+        let token_a = match self.pools.get(pool_id) {
+            Some(pool) => pool.token_a.clone(),
+            None => return trades,
+        };
+
+        let remaining_orders: Vec<LimitOrder> = match self.order_books.get_mut(pool_id) {
+            Some(book) => {
+                let mut orders: Vec<LimitOrder> = book.bids.drain(..).chain(book.asks.drain(..)).collect();
+                orders.retain(|o| o.remaining_in > 0);
+                orders
+            }
+            None => return trades,
+        };
+
+        for order in remaining_orders {
+            let out = self.instant_swap(pool_id.to_string(), order.token_in.clone(), order.remaining_in);
+            if out == 0 {
+                continue;
+            }
+
+            let is_ask = order.token_in == token_a;
+            let (base_amount, quote_amount) = if is_ask {
+                (order.remaining_in, out)
+            } else {
+                (out, order.remaining_in)
+            };
+            let (buyer, seller) = if is_ask {
+                ("AMM".to_string(), order.trader)
+            } else {
+                (order.trader, "AMM".to_string())
+            };
+
+            trades.push(TradeEvent {
+                pool_id: pool_id.to_string(),
+                base_amount,
+                quote_amount,
+                price: quote_amount as f64 / base_amount.max(1) as f64,
+                buyer,
+                seller,
+            });
+        }
+
+        trades
+    }
+}
+
+/// Fixed-point scale used to turn a `LimitOrder`'s `f64` price into an
+/// integer the digit-decomposition scheme can operate on.
+const PRICE_SCALE: f64 = 1_000_000.0;
+
+fn price_to_units(price: f64) -> u64 {
+    (price * PRICE_SCALE).round().max(0.0) as u64
+}
+
+/// Fixes a number's high-order digits (in `base`) and leaves the rest free
+/// - the set of every value whose top digits equal `prefix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigitPrefix {
+    pub prefix: Vec<u32>,
+    pub free_digits: usize,
+}
+
+fn to_base_digits(mut value: u64, base: u32, digits: usize) -> Vec<u32> {
+    let mut out = vec![0u32; digits];
+    for i in (0..digits).rev() {
+        out[i] = (value % base as u64) as u32;
+        value /= base as u64;
+    }
+    out
+}
+
+/// Decompose the inclusive integer range `[limit, max]` (over `digits`
+/// base-`base` digits) into the minimal set of digit-prefix-aligned blocks
+/// that exactly cover it - O(digits) intervals instead of enumerating
+/// every price point in the range.
+pub fn decompose_range(limit: u64, max: u64, base: u32, digits: usize) -> Vec<DigitPrefix> {
+    let mut intervals = Vec::new();
+    if limit > max {
+        return intervals;
+    }
+
+    let domain_max = (base as u64).pow(digits as u32).saturating_sub(1);
+    let hi = max.min(domain_max);
+    let mut lo = limit;
+
+    while lo <= hi {
+        // Largest aligned block starting at `lo` that still fits in [lo, hi].
+        let mut level = digits;
+        let mut block = (base as u64).pow(level as u32);
+        while level > 0 && (lo % block != 0 || lo.saturating_add(block - 1) > hi) {
+            level -= 1;
+            block = (base as u64).pow(level as u32);
+        }
+
+        let fixed_digits = digits - level;
+        let prefix_value = lo / block;
+        intervals.push(DigitPrefix {
+            prefix: to_base_digits(prefix_value, base, fixed_digits),
+            free_digits: level,
+        });
+
+        lo += block;
+    }
+
+    intervals
+}
+
+/// Price outcome an oracle attests to, expressed as per-position digits in
+/// a fixed base rather than one signature per possible price - mirroring
+/// how Discreet Log Contracts commit to numeric oracle outcomes digit by
+/// digit.
+#[derive(Debug, Clone)]
+pub struct OracleDigitAnnouncement {
+    pub digits: Vec<u32>,
+    pub signature: String,
+}
+
+fn prefix_matches(announced: &[u32], prefix: &DigitPrefix) -> bool {
+    announced.len() >= prefix.prefix.len() && announced[..prefix.prefix.len()] == prefix.prefix[..]
+}
+
+/// Settle `order` against `announcement`. The "filled" region (price >=
+/// order.price) is pre-covered by `decompose_range`; the order fills if
+/// the oracle's announced digits match any covering interval's fixed
+/// prefix, which avoids comparing against every possible price directly.
+pub fn settle_order(
+    order: &mut LimitOrder,
+    announcement: &OracleDigitAnnouncement,
+    base: u32,
+    digits: usize,
+) -> Result<bool, String> {
+    if announcement.signature.is_empty() {
+        return Err("missing oracle signature".to_string());
+    }
+    if announcement.digits.len() != digits {
+        return Err(format!(
+            "expected {} announced digits, got {}",
+            digits,
+            announcement.digits.len()
+        ));
+    }
+
+    if order.remaining_in == 0 {
+        return Ok(false);
+    }
+
+    let limit_units = price_to_units(order.price);
+    let max_units = (base as u64).pow(digits as u32) - 1;
+    let covering = decompose_range(limit_units, max_units, base, digits);
+
+    let filled = covering.iter().any(|interval| prefix_matches(&announcement.digits, interval));
+    if filled {
+        order.remaining_in = 0;
+    }
+
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_order(price: f64) -> LimitOrder {
+        LimitOrder {
+            id: "order-1".to_string(),
+            trader: "0xAlice".to_string(),
+            token_in: "USDC".to_string(),
+            token_out: "NUSA".to_string(),
+            amount_in: 100,
+            price,
+            remaining_in: 100,
+            sequence: 1,
+        }
+    }
+
+    fn expand(prefix: &DigitPrefix, base: u32) -> Vec<u64> {
+        let count = (base as u64).pow(prefix.free_digits as u32);
+        let prefix_value = prefix
+            .prefix
+            .iter()
+            .fold(0u64, |acc, &d| acc * base as u64 + d as u64);
+        (0..count).map(|i| prefix_value * count + i).collect()
+    }
+
+    #[test]
+    fn decompose_range_exactly_covers_without_overlap() {
+        let base = 4;
+        let digits = 3; // domain is 0..=63
+
+        let intervals = decompose_range(10, 63, base, digits);
+        let mut covered: Vec<u64> = intervals.iter().flat_map(|p| expand(p, base)).collect();
+        covered.sort();
+
+        let expected: Vec<u64> = (10..=63).collect();
+        assert_eq!(covered, expected);
+        // O(digits), not O(range): far fewer intervals than covered points.
+        assert!(intervals.len() < expected.len());
+    }
+
+    #[test]
+    fn decompose_range_empty_when_limit_above_max() {
+        assert!(decompose_range(50, 10, 10, 4).is_empty());
+    }
+
+    #[test]
+    fn settle_order_fills_when_oracle_price_meets_limit() {
+        let mut order = sample_order(2.5);
+        let base = 10;
+        let digits = 8;
+
+        let announcement = OracleDigitAnnouncement {
+            digits: to_base_digits(price_to_units(3.0), base, digits),
+            signature: "oracle-sig".to_string(),
+        };
+
+        let filled = settle_order(&mut order, &announcement, base, digits).unwrap();
+        assert!(filled);
+        assert_eq!(order.remaining_in, 0);
+    }
+
+    #[test]
+    fn settle_order_does_not_fill_when_oracle_price_below_limit() {
+        let mut order = sample_order(2.5);
+        let base = 10;
+        let digits = 8;
+
+        let announcement = OracleDigitAnnouncement {
+            digits: to_base_digits(price_to_units(2.0), base, digits),
+            signature: "oracle-sig".to_string(),
+        };
+
+        let filled = settle_order(&mut order, &announcement, base, digits).unwrap();
+        assert!(!filled);
+        assert_eq!(order.remaining_in, 100);
+    }
+
+    #[test]
+    fn settle_order_rejects_missing_signature() {
+        let mut order = sample_order(2.5);
+        let announcement = OracleDigitAnnouncement {
+            digits: to_base_digits(price_to_units(3.0), 10, 8),
+            signature: String::new(),
+        };
+
+        assert!(settle_order(&mut order, &announcement, 10, 8).is_err());
+        assert_eq!(order.remaining_in, 100);
+    }
+
+    #[test]
+    fn settle_order_rejects_wrong_digit_count() {
+        let mut order = sample_order(2.5);
+        let announcement = OracleDigitAnnouncement {
+            digits: vec![1, 2, 3],
+            signature: "oracle-sig".to_string(),
+        };
+
+        assert!(settle_order(&mut order, &announcement, 10, 8).is_err());
+    }
+
+    #[test]
+    fn settle_order_is_idempotent_once_filled() {
+        let mut order = sample_order(2.5);
+        let base = 10;
+        let digits = 8;
+        let announcement = OracleDigitAnnouncement {
+            digits: to_base_digits(price_to_units(3.0), base, digits),
+            signature: "oracle-sig".to_string(),
+        };
+
+        assert!(settle_order(&mut order, &announcement, base, digits).unwrap());
+        // Already filled - re-settling should be a no-op, not a re-fill.
+        assert!(!settle_order(&mut order, &announcement, base, digits).unwrap());
+    }
+
+    fn dex_with_pool() -> NusaDEX {
+        let mut dex = NusaDEX::new();
+        dex.create_pool("NUSA".to_string(), "USDC".to_string());
+        dex
+    }
+
+    #[test]
+    fn place_limit_order_rejects_unknown_token() {
+        let mut dex = dex_with_pool();
+        let result = dex.place_limit_order("NUSA-USDC", "alice".to_string(), "ETH".to_string(), 10, 2.0);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn match_orders_does_not_panic_on_a_nan_price() {
+        let mut dex = dex_with_pool();
+        dex.place_limit_order("NUSA-USDC", "seller".to_string(), "NUSA".to_string(), 10, f64::NAN).unwrap();
+        dex.place_limit_order("NUSA-USDC", "buyer".to_string(), "USDC".to_string(), 20, 2.0).unwrap();
+
+        // Should sort without panicking; the NaN order simply doesn't cross.
+        let _ = dex.match_orders("NUSA-USDC", false);
+    }
+
+    #[test]
+    fn match_orders_fully_fills_crossing_bid_and_ask() {
+        let mut dex = dex_with_pool();
+        // Ask: sell 10 NUSA at >= 2.0 USDC each.
+        dex.place_limit_order("NUSA-USDC", "seller".to_string(), "NUSA".to_string(), 10, 2.0).unwrap();
+        // Bid: buy NUSA, willing to pay up to 2.0 USDC each, spending 20 USDC.
+        dex.place_limit_order("NUSA-USDC", "buyer".to_string(), "USDC".to_string(), 20, 2.0).unwrap();
+
+        let trades = dex.match_orders("NUSA-USDC", false);
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].base_amount, 10);
+        assert_eq!(trades[0].quote_amount, 20);
+        assert_eq!(trades[0].price, 2.0);
+        assert_eq!(trades[0].buyer, "buyer");
+        assert_eq!(trades[0].seller, "seller");
+    }
+
+    #[test]
+    fn match_orders_partially_fills_when_sizes_differ() {
+        let mut dex = dex_with_pool();
+        dex.place_limit_order("NUSA-USDC", "seller".to_string(), "NUSA".to_string(), 10, 2.0).unwrap();
+        // Bid only wants to buy 4 NUSA worth (8 USDC).
+        dex.place_limit_order("NUSA-USDC", "buyer".to_string(), "USDC".to_string(), 8, 2.0).unwrap();
+
+        let trades = dex.match_orders("NUSA-USDC", false);
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].base_amount, 4);
+
+        // The ask still has 6 NUSA resting - a second matching bid can fill it.
+        dex.place_limit_order("NUSA-USDC", "buyer2".to_string(), "USDC".to_string(), 12, 2.0).unwrap();
+        let trades = dex.match_orders("NUSA-USDC", false);
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].base_amount, 6);
+    }
+
+    #[test]
+    fn match_orders_does_not_cross_when_bid_below_ask() {
+        let mut dex = dex_with_pool();
+        dex.place_limit_order("NUSA-USDC", "seller".to_string(), "NUSA".to_string(), 10, 3.0).unwrap();
+        dex.place_limit_order("NUSA-USDC", "buyer".to_string(), "USDC".to_string(), 20, 2.0).unwrap();
+
+        let trades = dex.match_orders("NUSA-USDC", false);
+        assert!(trades.is_empty());
+    }
+
+    #[test]
+    fn match_orders_routes_uncrossed_remainder_to_amm_when_enabled() {
+        let mut dex = dex_with_pool();
+        if let Some(pool) = dex.pools.get_mut("NUSA-USDC") {
+            pool.add_liquidity(1_000, 2_000);
+        }
+
+        dex.place_limit_order("NUSA-USDC", "seller".to_string(), "NUSA".to_string(), 10, 3.0).unwrap();
+        dex.place_limit_order("NUSA-USDC", "buyer".to_string(), "USDC".to_string(), 20, 2.0).unwrap();
+
+        let trades = dex.match_orders("NUSA-USDC", true);
+        // Neither order crossed the other, but both should have been
+        // routed through the pool instead of sitting unfilled.
+        assert_eq!(trades.len(), 2);
+        assert!(trades.iter().any(|t| t.seller == "seller" && t.buyer == "AMM"));
+        assert!(trades.iter().any(|t| t.buyer == "buyer" && t.seller == "AMM"));
+    }
 }
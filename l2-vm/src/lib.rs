@@ -1,19 +1,180 @@
 //!  NUSA Chain L2 VM - WebAssembly Runtime
+//!
+//! Contracts follow the NEAR deploy-and-invoke model: a wasm blob is
+//! deployed under an account's `code` and later invoked by export name
+//! with serialized args. Host imports under the `env` module give the
+//! contract read/write access to that account's persistent storage and
+//! balance and a log sink, and execution is metered in wasmtime fuel so
+//! `TransactionExecutor` can charge real gas for a call instead of a flat
+//! fee.
 
+use std::collections::HashMap;
 use wasmtime::*;
 
+/// Memory offset call args are written to before invoking a contract's
+/// export. This runtime doesn't support guest-driven allocation yet, so
+/// args land at a fixed scratch offset rather than one the guest requests.
+const ARGS_OFFSET: u32 = 1024;
+
+/// The account-shaped state a deployed contract can touch through host
+/// imports. Mirrors `core::state::Account` in the main chain crate, kept
+/// independent here since this crate has no dependency on it - storage
+/// values are hex-encoded bytes, same convention `Account::storage`
+/// already uses.
+#[derive(Debug, Clone, Default)]
+pub struct ContractAccount {
+    pub balance: u64,
+    pub storage: HashMap<String, String>,
+}
+
+/// What a single `NusaVM::execute` call produced: the contract's declared
+/// result bytes, the fuel it actually burned, its mutated account state,
+/// and anything it logged. `TransactionExecutor` folds these into a
+/// `TransactionReceipt`.
+pub struct ExecutionOutcome {
+    pub result: Vec<u8>,
+    pub gas_used: u64,
+    pub account: ContractAccount,
+    pub logs: Vec<String>,
+}
+
+/// Environment threaded through a single `execute` call via wasmtime's
+/// `Store` data - the invoked account's state plus whatever the contract
+/// logs or returns along the way.
+struct HostEnv {
+    account: ContractAccount,
+    logs: Vec<String>,
+    result: Vec<u8>,
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .filter_map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
 pub struct NusaVM {
     engine: Engine,
 }
 
 impl NusaVM {
     pub fn new() -> Self {
-        let engine = Engine::default();
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).expect("fuel metering is a valid wasmtime config");
         Self { engine }
     }
 
-    pub fn execute(&self, _wasm_bytes: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
-        Ok("VM execution placeholder".to_string())
+    /// Instantiate `wasm_bytes`, invoke its `method` export with `args`,
+    /// and report the bytes the contract reported as its result, the fuel
+    /// actually burned, and its (possibly mutated) account state.
+    pub fn execute(
+        &self,
+        wasm_bytes: &[u8],
+        account: ContractAccount,
+        method: &str,
+        args: &[u8],
+        gas_limit: u64,
+    ) -> Result<ExecutionOutcome, String> {
+        let module = Module::new(&self.engine, wasm_bytes).map_err(|e| format!("invalid wasm module: {}", e))?;
+
+        let env = HostEnv { account, logs: Vec::new(), result: Vec::new() };
+        let mut store = Store::new(&self.engine, env);
+        store.add_fuel(gas_limit).map_err(|e| format!("failed to meter gas: {}", e))?;
+
+        let mut linker: Linker<HostEnv> = Linker::new(&self.engine);
+        Self::link_host_functions(&mut linker).map_err(|e| format!("failed to register host functions: {}", e))?;
+
+        let instance = linker
+            .instantiate(&mut store, &module)
+            .map_err(|e| format!("instantiation failed: {}", e))?;
+
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| "module does not export linear memory".to_string())?;
+        memory
+            .write(&mut store, ARGS_OFFSET as usize, args)
+            .map_err(|e| format!("failed to pass call args into guest memory: {}", e))?;
+
+        let entry = instance
+            .get_typed_func::<(i32, i32), ()>(&mut store, method)
+            .map_err(|e| format!("method '{}' not found or has the wrong signature: {}", method, e))?;
+
+        entry
+            .call(&mut store, (ARGS_OFFSET as i32, args.len() as i32))
+            .map_err(|e| format!("contract call to '{}' trapped: {}", method, e))?;
+
+        let gas_used = store.fuel_consumed().unwrap_or(gas_limit);
+        let env = store.into_data();
+
+        Ok(ExecutionOutcome {
+            result: env.result,
+            gas_used,
+            account: env.account,
+            logs: env.logs,
+        })
+    }
+
+    fn link_host_functions(linker: &mut Linker<HostEnv>) -> Result<()> {
+        linker.func_wrap(
+            "env",
+            "storage_write",
+            |mut caller: Caller<'_, HostEnv>, key_ptr: i32, key_len: i32, val_ptr: i32, val_len: i32| {
+                let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else { return };
+                let key = memory.data(&caller)[key_ptr as usize..(key_ptr + key_len) as usize].to_vec();
+                let value = memory.data(&caller)[val_ptr as usize..(val_ptr + val_len) as usize].to_vec();
+                let key = String::from_utf8_lossy(&key).to_string();
+                caller.data_mut().account.storage.insert(key, hex_encode(&value));
+            },
+        )?;
+
+        linker.func_wrap(
+            "env",
+            "storage_read",
+            |mut caller: Caller<'_, HostEnv>, key_ptr: i32, key_len: i32, out_ptr: i32| -> i32 {
+                let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else { return -1 };
+                let key = memory.data(&caller)[key_ptr as usize..(key_ptr + key_len) as usize].to_vec();
+                let key = String::from_utf8_lossy(&key).to_string();
+                let Some(encoded) = caller.data().account.storage.get(&key).cloned() else { return -1 };
+                let value = hex_decode(&encoded);
+                if memory.write(&mut caller, out_ptr as usize, &value).is_err() {
+                    return -1;
+                }
+                value.len() as i32
+            },
+        )?;
+
+        linker.func_wrap("env", "get_balance", |caller: Caller<'_, HostEnv>| -> i64 {
+            caller.data().account.balance as i64
+        })?;
+
+        linker.func_wrap(
+            "env",
+            "log",
+            |mut caller: Caller<'_, HostEnv>, ptr: i32, len: i32| {
+                let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else { return };
+                let bytes = memory.data(&caller)[ptr as usize..(ptr + len) as usize].to_vec();
+                let message = String::from_utf8_lossy(&bytes).to_string();
+                caller.data_mut().logs.push(message);
+            },
+        )?;
+
+        linker.func_wrap(
+            "env",
+            "set_result",
+            |mut caller: Caller<'_, HostEnv>, ptr: i32, len: i32| {
+                let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else { return };
+                let bytes = memory.data(&caller)[ptr as usize..(ptr + len) as usize].to_vec();
+                caller.data_mut().result = bytes;
+            },
+        )?;
+
+        Ok(())
     }
 }
 
@@ -1,7 +1,27 @@
 // NUSA NFT Marketplace - Buy, sell, auction NFTs
 // Features: Royalties, auctions, collections, rarity system
+//
+// NFT, listing, auction and collection state lives behind a
+// `MarketplaceStorage` trait rather than in-memory `HashMap`s, so the same
+// marketplace logic runs against a real database on a server node or
+// against the browser's IndexedDB when compiled to wasm32 - mirroring the
+// native/wasm storage split in Komodo's `nft_storage` module. HTLC locks,
+// accepted denoms, per-denom stats and mint commitments are held in memory
+// only.
 
 use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
 
 #[derive(Debug, Clone)]
 pub struct NFT {
@@ -33,19 +53,12 @@ pub struct Auction {
     pub seller: String,
     pub starting_bid: u64,
     pub current_bid: u64,
+    pub currency: String,
     pub highest_bidder: String,
     pub end_time: u64,
     pub active: bool,
 }
 
-pub struct NFTMarketplace {
-    nfts: HashMap<String, NFT>,
-    listings: HashMap<String, Listing>,
-    auctions: HashMap<String, Auction>,
-    collections: HashMap<String, Collection>,
-    marketplace_fee: f64,  // 2.5% fee
-}
-
 #[derive(Debug, Clone)]
 pub struct Collection {
     pub id: String,
@@ -57,19 +70,703 @@ pub struct Collection {
     pub verified: bool,
 }
 
+// The trait is declared once per target so the `Send + Sync` bound can
+// differ: the wasm32 IndexedDB backend shuttles `JsValue`s that aren't
+// `Send`, while the native sqlite backend needs `Send + Sync` to live
+// behind an `Arc`/`Box<dyn _>` shared across tokio tasks.
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+pub trait MarketplaceStorage: Send + Sync {
+    async fn put_nft(&self, nft: NFT) -> Result<(), String>;
+    async fn get_nft(&self, token_id: &str) -> Result<Option<NFT>, String>;
+    async fn all_nfts(&self) -> Result<Vec<NFT>, String>;
+
+    async fn put_listing(&self, listing: Listing) -> Result<(), String>;
+    async fn get_listing(&self, nft_id: &str) -> Result<Option<Listing>, String>;
+    async fn all_listings(&self) -> Result<Vec<Listing>, String>;
+
+    async fn put_auction(&self, auction: Auction) -> Result<(), String>;
+    async fn get_auction(&self, nft_id: &str) -> Result<Option<Auction>, String>;
+    async fn all_auctions(&self) -> Result<Vec<Auction>, String>;
+
+    async fn put_collection(&self, collection: Collection) -> Result<(), String>;
+    async fn get_collection(&self, id: &str) -> Result<Option<Collection>, String>;
+
+    async fn nft_count(&self) -> Result<usize, String>;
+    async fn collection_count(&self) -> Result<usize, String>;
+}
+
+#[cfg(target_arch = "wasm32")]
+#[async_trait::async_trait(?Send)]
+pub trait MarketplaceStorage {
+    async fn put_nft(&self, nft: NFT) -> Result<(), String>;
+    async fn get_nft(&self, token_id: &str) -> Result<Option<NFT>, String>;
+    async fn all_nfts(&self) -> Result<Vec<NFT>, String>;
+
+    async fn put_listing(&self, listing: Listing) -> Result<(), String>;
+    async fn get_listing(&self, nft_id: &str) -> Result<Option<Listing>, String>;
+    async fn all_listings(&self) -> Result<Vec<Listing>, String>;
+
+    async fn put_auction(&self, auction: Auction) -> Result<(), String>;
+    async fn get_auction(&self, nft_id: &str) -> Result<Option<Auction>, String>;
+    async fn all_auctions(&self) -> Result<Vec<Auction>, String>;
+
+    async fn put_collection(&self, collection: Collection) -> Result<(), String>;
+    async fn get_collection(&self, id: &str) -> Result<Option<Collection>, String>;
+
+    async fn nft_count(&self) -> Result<usize, String>;
+    async fn collection_count(&self) -> Result<usize, String>;
+}
+
+/// Native backend: every marketplace table lives in a sqlite database.
+/// `rusqlite` is synchronous, so each call just takes the connection
+/// mutex rather than actually yielding - the trait stays `async` so wasm
+/// callers (genuinely async against IndexedDB) can share the same API.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct SqlMarketplaceStorage {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SqlMarketplaceStorage {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = rusqlite::Connection::open(path).map_err(|e| format!("failed to open marketplace db: {}", e))?;
+        Self::init_schema(&conn)?;
+        Ok(Self { conn: std::sync::Mutex::new(conn) })
+    }
+
+    pub fn in_memory() -> Result<Self, String> {
+        let conn = rusqlite::Connection::open_in_memory().map_err(|e| format!("failed to open in-memory marketplace db: {}", e))?;
+        Self::init_schema(&conn)?;
+        Ok(Self { conn: std::sync::Mutex::new(conn) })
+    }
+
+    fn init_schema(conn: &rusqlite::Connection) -> Result<(), String> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS nfts (
+                token_id TEXT PRIMARY KEY,
+                collection TEXT NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL,
+                image_uri TEXT NOT NULL,
+                owner TEXT NOT NULL,
+                creator TEXT NOT NULL,
+                royalty_percentage REAL NOT NULL,
+                rarity_score INTEGER NOT NULL,
+                attributes TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS listings (
+                nft_id TEXT PRIMARY KEY,
+                seller TEXT NOT NULL,
+                price INTEGER NOT NULL,
+                currency TEXT NOT NULL,
+                listed_at INTEGER NOT NULL,
+                active INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS auctions (
+                nft_id TEXT PRIMARY KEY,
+                seller TEXT NOT NULL,
+                starting_bid INTEGER NOT NULL,
+                current_bid INTEGER NOT NULL,
+                currency TEXT NOT NULL,
+                highest_bidder TEXT NOT NULL,
+                end_time INTEGER NOT NULL,
+                active INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS collections (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                creator TEXT NOT NULL,
+                total_items INTEGER NOT NULL,
+                floor_price INTEGER NOT NULL,
+                total_volume INTEGER NOT NULL,
+                verified INTEGER NOT NULL
+            );",
+        )
+        .map_err(|e| format!("failed to initialize marketplace schema: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait::async_trait]
+impl MarketplaceStorage for SqlMarketplaceStorage {
+    async fn put_nft(&self, nft: NFT) -> Result<(), String> {
+        let attributes = serde_json::to_string(&nft.attributes).map_err(|e| e.to_string())?;
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO nfts (token_id, collection, name, description, image_uri, owner, creator, royalty_percentage, rarity_score, attributes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(token_id) DO UPDATE SET
+                collection = excluded.collection, name = excluded.name, description = excluded.description,
+                image_uri = excluded.image_uri, owner = excluded.owner, creator = excluded.creator,
+                royalty_percentage = excluded.royalty_percentage, rarity_score = excluded.rarity_score,
+                attributes = excluded.attributes",
+            rusqlite::params![
+                nft.token_id, nft.collection, nft.name, nft.description, nft.image_uri,
+                nft.owner, nft.creator, nft.royalty_percentage, nft.rarity_score as i64, attributes,
+            ],
+        )
+        .map_err(|e| format!("failed to store NFT: {}", e))?;
+        Ok(())
+    }
+
+    async fn get_nft(&self, token_id: &str) -> Result<Option<NFT>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let result = conn.query_row(
+            "SELECT token_id, collection, name, description, image_uri, owner, creator, royalty_percentage, rarity_score, attributes
+             FROM nfts WHERE token_id = ?1",
+            rusqlite::params![token_id],
+            Self::row_to_nft,
+        );
+        match result {
+            Ok(nft) => Ok(Some(nft)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("failed to load NFT: {}", e)),
+        }
+    }
+
+    async fn all_nfts(&self) -> Result<Vec<NFT>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT token_id, collection, name, description, image_uri, owner, creator, royalty_percentage, rarity_score, attributes FROM nfts")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], Self::row_to_nft)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("failed to load NFTs: {}", e))
+    }
+
+    async fn put_listing(&self, listing: Listing) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO listings (nft_id, seller, price, currency, listed_at, active)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(nft_id) DO UPDATE SET
+                seller = excluded.seller, price = excluded.price, currency = excluded.currency,
+                listed_at = excluded.listed_at, active = excluded.active",
+            rusqlite::params![listing.nft_id, listing.seller, listing.price as i64, listing.currency, listing.listed_at as i64, listing.active],
+        )
+        .map_err(|e| format!("failed to store listing: {}", e))?;
+        Ok(())
+    }
+
+    async fn get_listing(&self, nft_id: &str) -> Result<Option<Listing>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let result = conn.query_row(
+            "SELECT nft_id, seller, price, currency, listed_at, active FROM listings WHERE nft_id = ?1",
+            rusqlite::params![nft_id],
+            |row| {
+                Ok(Listing {
+                    nft_id: row.get(0)?,
+                    seller: row.get(1)?,
+                    price: row.get::<_, i64>(2)? as u64,
+                    currency: row.get(3)?,
+                    listed_at: row.get::<_, i64>(4)? as u64,
+                    active: row.get(5)?,
+                })
+            },
+        );
+        match result {
+            Ok(listing) => Ok(Some(listing)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("failed to load listing: {}", e)),
+        }
+    }
+
+    async fn all_listings(&self) -> Result<Vec<Listing>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT nft_id, seller, price, currency, listed_at, active FROM listings")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Listing {
+                    nft_id: row.get(0)?,
+                    seller: row.get(1)?,
+                    price: row.get::<_, i64>(2)? as u64,
+                    currency: row.get(3)?,
+                    listed_at: row.get::<_, i64>(4)? as u64,
+                    active: row.get(5)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("failed to load listings: {}", e))
+    }
+
+    async fn put_auction(&self, auction: Auction) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO auctions (nft_id, seller, starting_bid, current_bid, currency, highest_bidder, end_time, active)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(nft_id) DO UPDATE SET
+                seller = excluded.seller, starting_bid = excluded.starting_bid, current_bid = excluded.current_bid,
+                currency = excluded.currency, highest_bidder = excluded.highest_bidder, end_time = excluded.end_time, active = excluded.active",
+            rusqlite::params![
+                auction.nft_id, auction.seller, auction.starting_bid as i64, auction.current_bid as i64,
+                auction.currency, auction.highest_bidder, auction.end_time as i64, auction.active,
+            ],
+        )
+        .map_err(|e| format!("failed to store auction: {}", e))?;
+        Ok(())
+    }
+
+    async fn get_auction(&self, nft_id: &str) -> Result<Option<Auction>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let result = conn.query_row(
+            "SELECT nft_id, seller, starting_bid, current_bid, currency, highest_bidder, end_time, active FROM auctions WHERE nft_id = ?1",
+            rusqlite::params![nft_id],
+            |row| {
+                Ok(Auction {
+                    nft_id: row.get(0)?,
+                    seller: row.get(1)?,
+                    starting_bid: row.get::<_, i64>(2)? as u64,
+                    current_bid: row.get::<_, i64>(3)? as u64,
+                    currency: row.get(4)?,
+                    highest_bidder: row.get(5)?,
+                    end_time: row.get::<_, i64>(6)? as u64,
+                    active: row.get(7)?,
+                })
+            },
+        );
+        match result {
+            Ok(auction) => Ok(Some(auction)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("failed to load auction: {}", e)),
+        }
+    }
+
+    async fn all_auctions(&self) -> Result<Vec<Auction>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut stmt = conn
+            .prepare("SELECT nft_id, seller, starting_bid, current_bid, currency, highest_bidder, end_time, active FROM auctions")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Auction {
+                    nft_id: row.get(0)?,
+                    seller: row.get(1)?,
+                    starting_bid: row.get::<_, i64>(2)? as u64,
+                    current_bid: row.get::<_, i64>(3)? as u64,
+                    currency: row.get(4)?,
+                    highest_bidder: row.get(5)?,
+                    end_time: row.get::<_, i64>(6)? as u64,
+                    active: row.get(7)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| format!("failed to load auctions: {}", e))
+    }
+
+    async fn put_collection(&self, collection: Collection) -> Result<(), String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO collections (id, name, creator, total_items, floor_price, total_volume, verified)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name, creator = excluded.creator, total_items = excluded.total_items,
+                floor_price = excluded.floor_price, total_volume = excluded.total_volume, verified = excluded.verified",
+            rusqlite::params![
+                collection.id, collection.name, collection.creator, collection.total_items as i64,
+                collection.floor_price as i64, collection.total_volume as i64, collection.verified,
+            ],
+        )
+        .map_err(|e| format!("failed to store collection: {}", e))?;
+        Ok(())
+    }
+
+    async fn get_collection(&self, id: &str) -> Result<Option<Collection>, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let result = conn.query_row(
+            "SELECT id, name, creator, total_items, floor_price, total_volume, verified FROM collections WHERE id = ?1",
+            rusqlite::params![id],
+            |row| {
+                Ok(Collection {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    creator: row.get(2)?,
+                    total_items: row.get::<_, i64>(3)? as u64,
+                    floor_price: row.get::<_, i64>(4)? as u64,
+                    total_volume: row.get::<_, i64>(5)? as u64,
+                    verified: row.get(6)?,
+                })
+            },
+        );
+        match result {
+            Ok(collection) => Ok(Some(collection)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("failed to load collection: {}", e)),
+        }
+    }
+
+    async fn nft_count(&self) -> Result<usize, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row("SELECT COUNT(*) FROM nfts", [], |row| row.get::<_, i64>(0))
+            .map(|n| n as usize)
+            .map_err(|e| format!("failed to count NFTs: {}", e))
+    }
+
+    async fn collection_count(&self) -> Result<usize, String> {
+        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        conn.query_row("SELECT COUNT(*) FROM collections", [], |row| row.get::<_, i64>(0))
+            .map(|n| n as usize)
+            .map_err(|e| format!("failed to count collections: {}", e))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SqlMarketplaceStorage {
+    fn row_to_nft(row: &rusqlite::Row) -> rusqlite::Result<NFT> {
+        let attributes: String = row.get(9)?;
+        let attributes: HashMap<String, String> = serde_json::from_str(&attributes).unwrap_or_default();
+        Ok(NFT {
+            token_id: row.get(0)?,
+            collection: row.get(1)?,
+            name: row.get(2)?,
+            description: row.get(3)?,
+            image_uri: row.get(4)?,
+            owner: row.get(5)?,
+            creator: row.get(6)?,
+            royalty_percentage: row.get(7)?,
+            rarity_score: row.get::<_, i64>(8)? as u64,
+            attributes,
+        })
+    }
+}
+
+/// wasm32 backend: every marketplace table is an IndexedDB object store,
+/// reached through `rexie`'s promise-based bindings.
+#[cfg(target_arch = "wasm32")]
+pub struct IndexedDbMarketplaceStorage {
+    db: rexie::Rexie,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl IndexedDbMarketplaceStorage {
+    pub async fn open() -> Result<Self, String> {
+        let db = rexie::Rexie::builder("nusa_marketplace")
+            .version(1)
+            .add_object_store(rexie::ObjectStore::new("nfts").key_path("token_id"))
+            .add_object_store(rexie::ObjectStore::new("listings").key_path("nft_id"))
+            .add_object_store(rexie::ObjectStore::new("auctions").key_path("nft_id"))
+            .add_object_store(rexie::ObjectStore::new("collections").key_path("id"))
+            .build()
+            .await
+            .map_err(|e| format!("failed to open IndexedDB database: {:?}", e))?;
+        Ok(Self { db })
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, store: &str, key: &str) -> Result<Option<T>, String> {
+        let tx = self.db.transaction(&[store], rexie::TransactionMode::ReadOnly).map_err(|e| format!("{:?}", e))?;
+        let object_store = tx.store(store).map_err(|e| format!("{:?}", e))?;
+        let key = wasm_bindgen::JsValue::from_str(key);
+        let value = object_store.get(key).await.map_err(|e| format!("{:?}", e))?;
+        if value.is_undefined() || value.is_null() {
+            return Ok(None);
+        }
+        serde_wasm_bindgen::from_value(value).map(Some).map_err(|e| format!("{:?}", e))
+    }
+
+    async fn put<T: serde::Serialize>(&self, store: &str, value: &T) -> Result<(), String> {
+        let tx = self.db.transaction(&[store], rexie::TransactionMode::ReadWrite).map_err(|e| format!("{:?}", e))?;
+        let object_store = tx.store(store).map_err(|e| format!("{:?}", e))?;
+        let js_value = serde_wasm_bindgen::to_value(value).map_err(|e| format!("{:?}", e))?;
+        object_store.put(&js_value, None).await.map_err(|e| format!("{:?}", e))?;
+        tx.done().await.map_err(|e| format!("{:?}", e))?;
+        Ok(())
+    }
+
+    async fn count(&self, store: &str) -> Result<usize, String> {
+        let tx = self.db.transaction(&[store], rexie::TransactionMode::ReadOnly).map_err(|e| format!("{:?}", e))?;
+        let object_store = tx.store(store).map_err(|e| format!("{:?}", e))?;
+        object_store.count(None).await.map(|n| n as usize).map_err(|e| format!("{:?}", e))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[async_trait::async_trait(?Send)]
+impl MarketplaceStorage for IndexedDbMarketplaceStorage {
+    async fn put_nft(&self, nft: NFT) -> Result<(), String> { self.put("nfts", &nft).await }
+    async fn get_nft(&self, token_id: &str) -> Result<Option<NFT>, String> { self.get("nfts", token_id).await }
+    async fn all_nfts(&self) -> Result<Vec<NFT>, String> { Err("IndexedDB scan-all is not implemented - look up by token_id instead".to_string()) }
+
+    async fn put_listing(&self, listing: Listing) -> Result<(), String> { self.put("listings", &listing).await }
+    async fn get_listing(&self, nft_id: &str) -> Result<Option<Listing>, String> { self.get("listings", nft_id).await }
+    async fn all_listings(&self) -> Result<Vec<Listing>, String> { Err("IndexedDB scan-all is not implemented - look up by nft_id instead".to_string()) }
+
+    async fn put_auction(&self, auction: Auction) -> Result<(), String> { self.put("auctions", &auction).await }
+    async fn get_auction(&self, nft_id: &str) -> Result<Option<Auction>, String> { self.get("auctions", nft_id).await }
+    async fn all_auctions(&self) -> Result<Vec<Auction>, String> { Err("IndexedDB scan-all is not implemented - look up by nft_id instead".to_string()) }
+
+    async fn put_collection(&self, collection: Collection) -> Result<(), String> { self.put("collections", &collection).await }
+    async fn get_collection(&self, id: &str) -> Result<Option<Collection>, String> { self.get("collections", id).await }
+
+    async fn nft_count(&self) -> Result<usize, String> { self.count("nfts").await }
+    async fn collection_count(&self) -> Result<usize, String> { self.count("collections").await }
+}
+
+/// A hashed-timelock lock on one NFT, following the xmr-btc atomic-swap
+/// construction: `maker` can `refund_htlc()` once `timelock` (unix seconds)
+/// passes, otherwise whoever reveals a preimage of `hashlock` via
+/// `redeem_htlc()` claims the NFT on `taker`'s behalf.
+#[derive(Debug, Clone)]
+pub struct HtlcLock {
+    pub id: String,
+    pub hashlock: [u8; 32],
+    pub timelock: u64,
+    pub maker: String,
+    pub taker: String,
+    pub asset: String,
+    pub redeemed: bool,
+    pub refunded: bool,
+}
+
+/// An accepted payment denomination, analogous to the allow-listed
+/// native/IBC denoms in the Cosmos coin-flip contract. `decimals` is
+/// advisory display metadata only - amounts everywhere else in the
+/// marketplace stay denominated in the smallest unit.
+#[derive(Debug, Clone)]
+pub struct Denom {
+    pub symbol: String,
+    pub decimals: u8,
+    pub enabled: bool,
+}
+
+/// Floor price and lifetime volume for one (collection, denom) pair.
+#[derive(Debug, Clone, Default)]
+struct DenomStats {
+    floor_price: u64,
+    total_volume: u64,
+}
+
+/// A commit-reveal request for fair, unriggable NFT minting - the minter
+/// submits `sha256(nonce || seed)` up front via `commit_mint`, and only
+/// once `reveal_block` is reached do they reveal `seed` through
+/// `reveal_mint`, at which point rarity and attributes are derived
+/// deterministically from it so no one (including the minter) can steer
+/// the outcome after the fact.
+#[derive(Debug, Clone)]
+pub struct MintCommitment {
+    pub id: String,
+    pub collection: String,
+    pub name: String,
+    pub description: String,
+    pub image_uri: String,
+    pub creator: String,
+    pub royalty_percentage: f64,
+    pub commitment: [u8; 32],
+    pub reveal_block: u64,
+    pub revealed: bool,
+}
+
+pub struct NFTMarketplace {
+    storage: Box<dyn MarketplaceStorage>,
+    marketplace_fee: f64,  // 2.5% fee
+    htlcs: tokio::sync::RwLock<HashMap<String, HtlcLock>>,
+    /// Address allowed to call the `admin_*` methods below.
+    admin: String,
+    denoms: tokio::sync::RwLock<HashMap<String, Denom>>,
+    denom_stats: tokio::sync::RwLock<HashMap<(String, String), DenomStats>>,
+    mint_commitments: tokio::sync::RwLock<HashMap<String, MintCommitment>>,
+}
+
 impl NFTMarketplace {
-    pub fn new() -> Self {
+    pub fn new(storage: Box<dyn MarketplaceStorage>, admin: String) -> Self {
+        let mut denoms = HashMap::new();
+        denoms.insert("NUSA".to_string(), Denom { symbol: "NUSA".to_string(), decimals: 18, enabled: true });
+
         Self {
-            nfts: HashMap::new(),
-            listings: HashMap::new(),
-            auctions: HashMap::new(),
-            collections: HashMap::new(),
+            storage,
             marketplace_fee: 0.025,  // 2.5%
+            htlcs: tokio::sync::RwLock::new(HashMap::new()),
+            admin,
+            denoms: tokio::sync::RwLock::new(denoms),
+            denom_stats: tokio::sync::RwLock::new(HashMap::new()),
+            mint_commitments: tokio::sync::RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn require_admin(&self, caller: &str) -> Result<(), String> {
+        if caller != self.admin {
+            return Err("caller is not the marketplace admin".to_string());
+        }
+        Ok(())
+    }
+
+    async fn is_denom_accepted(&self, symbol: &str) -> bool {
+        self.denoms.read().await.get(symbol).map(|d| d.enabled).unwrap_or(false)
+    }
+
+    async fn record_floor_price(&self, collection: &str, denom: &str, price: u64) {
+        let mut stats = self.denom_stats.write().await;
+        let entry = stats.entry((collection.to_string(), denom.to_string())).or_default();
+        if entry.floor_price == 0 || price < entry.floor_price {
+            entry.floor_price = price;
+        }
+    }
+
+    async fn record_volume(&self, collection: &str, denom: &str, amount: u64) {
+        let mut stats = self.denom_stats.write().await;
+        let entry = stats.entry((collection.to_string(), denom.to_string())).or_default();
+        entry.total_volume += amount;
+    }
+
+    /// Floor price and lifetime traded volume for `collection` in `denom`,
+    /// e.g. to show "floor: 12 NUSA / 0.5 NUSD" side by side in a storefront.
+    pub async fn denom_stats(&self, collection: &str, denom: &str) -> (u64, u64) {
+        let stats = self.denom_stats.read().await;
+        stats
+            .get(&(collection.to_string(), denom.to_string()))
+            .map(|s| (s.floor_price, s.total_volume))
+            .unwrap_or_default()
+    }
+
+    /// Registers (or re-enables) a denom that `list_nft`/`buy_nft`/
+    /// `create_auction` will accept as a currency.
+    pub async fn admin_add_denom(&mut self, caller: &str, symbol: String, decimals: u8) -> Result<(), String> {
+        self.require_admin(caller)?;
+        let mut denoms = self.denoms.write().await;
+        denoms.insert(symbol.clone(), Denom { symbol: symbol.clone(), decimals, enabled: true });
+        println!("⚙️ denom {} registered ({} decimals)", symbol, decimals);
+        Ok(())
+    }
+
+    /// Disables a denom - existing listings/auctions in it are untouched,
+    /// but new listings and purchases in it are rejected.
+    pub async fn admin_remove_denom(&mut self, caller: &str, symbol: &str) -> Result<(), String> {
+        self.require_admin(caller)?;
+        let mut denoms = self.denoms.write().await;
+        let denom = denoms.get_mut(symbol).ok_or("unknown denom")?;
+        denom.enabled = false;
+        println!("⚙️ denom {} disabled", symbol);
+        Ok(())
+    }
+
+    /// Adjusts the marketplace-wide cut taken from every sale.
+    pub async fn admin_set_fee(&mut self, caller: &str, new_fee: f64) -> Result<(), String> {
+        self.require_admin(caller)?;
+        if !(0.0..=1.0).contains(&new_fee) {
+            return Err("fee must be between 0 and 1".to_string());
+        }
+        self.marketplace_fee = new_fee;
+        println!("⚙️ marketplace fee updated to {:.2}%", new_fee * 100.0);
+        Ok(())
+    }
+
+    /// Marks a collection verified, e.g. after the admin confirms the
+    /// creator's identity off-chain.
+    pub async fn admin_verify_collection(&mut self, caller: &str, collection_id: &str) -> Result<(), String> {
+        self.require_admin(caller)?;
+        let mut collection = self.storage.get_collection(collection_id).await?.ok_or("unknown collection")?;
+        collection.verified = true;
+        self.storage.put_collection(collection).await?;
+        println!("✅ collection {} verified", collection_id);
+        Ok(())
+    }
+
+    /// Locks `nft_id` (owned by `maker`) against `hashlock`, refundable by
+    /// `maker` after `timelock` and redeemable by anyone who reveals a
+    /// matching preimage before then - the NFT side of a trustless
+    /// NFT-for-token or cross-chain swap.
+    pub async fn lock_htlc(&mut self, nft_id: String, maker: String, taker: String, hashlock: [u8; 32], timelock: u64) -> Result<String, String> {
+        let mut nft = self.storage.get_nft(&nft_id).await?.ok_or("NFT doesn't exist")?;
+        if nft.owner != maker {
+            return Err("caller does not own this NFT".to_string());
+        }
+
+        let lock_id = format!("htlc_{}", hex_encode(&hashlock));
+        let mut htlcs = self.htlcs.write().await;
+        if htlcs.contains_key(&lock_id) {
+            return Err("a lock already exists for this hashlock".to_string());
+        }
+
+        nft.owner = format!("htlc-escrow:{}", lock_id);
+        self.storage.put_nft(nft).await?;
+
+        htlcs.insert(lock_id.clone(), HtlcLock {
+            id: lock_id.clone(),
+            hashlock,
+            timelock,
+            maker,
+            taker,
+            asset: nft_id,
+            redeemed: false,
+            refunded: false,
+        });
+
+        println!("🔒 HTLC locked NFT under hashlock {}", hex_encode(&hashlock));
+
+        Ok(lock_id)
+    }
+
+    /// Reveals `preimage`; if `sha256(preimage) == hashlock` and the
+    /// timelock hasn't expired, transfers the locked NFT to the taker and
+    /// hands the preimage back so the counterparty's leg of the swap can
+    /// be redeemed with the same secret.
+    pub async fn redeem_htlc(&mut self, lock_id: &str, preimage: &[u8]) -> Result<Vec<u8>, String> {
+        let (taker, nft_id) = {
+            let mut htlcs = self.htlcs.write().await;
+            let lock = htlcs.get_mut(lock_id).ok_or("no such HTLC lock")?;
+
+            if lock.redeemed || lock.refunded {
+                return Err("lock already settled".to_string());
+            }
+            if now_secs() >= lock.timelock {
+                return Err("timelock has expired, only refund is allowed now".to_string());
+            }
+
+            let mut hasher = Sha256::new();
+            hasher.update(preimage);
+            let hash: [u8; 32] = hasher.finalize().into();
+            if hash != lock.hashlock {
+                return Err("preimage does not match hashlock".to_string());
+            }
+
+            lock.redeemed = true;
+            (lock.taker.clone(), lock.asset.clone())
+        };
+
+        if let Some(mut nft) = self.storage.get_nft(&nft_id).await? {
+            nft.owner = taker;
+            self.storage.put_nft(nft).await?;
         }
+
+        println!("🔓 HTLC redeemed: {} transferred to taker", nft_id);
+
+        Ok(preimage.to_vec())
+    }
+
+    /// Returns the locked NFT to `maker` once the timelock has expired -
+    /// guards against a counterparty who never reveals the preimage.
+    pub async fn refund_htlc(&mut self, lock_id: &str) -> Result<(), String> {
+        let (maker, nft_id) = {
+            let mut htlcs = self.htlcs.write().await;
+            let lock = htlcs.get_mut(lock_id).ok_or("no such HTLC lock")?;
+
+            if lock.redeemed || lock.refunded {
+                return Err("lock already settled".to_string());
+            }
+            if now_secs() < lock.timelock {
+                return Err("timelock has not expired yet".to_string());
+            }
+
+            lock.refunded = true;
+            (lock.maker.clone(), lock.asset.clone())
+        };
+
+        if let Some(mut nft) = self.storage.get_nft(&nft_id).await? {
+            nft.owner = maker;
+            self.storage.put_nft(nft).await?;
+        }
+
+        println!("↩️ HTLC refunded: {} returned to maker", nft_id);
+
+        Ok(())
     }
-    
+
     // Mint NFT
-    pub fn mint_nft(
+    pub async fn mint_nft(
         &mut self,
         collection: String,
         name: String,
@@ -77,9 +774,10 @@ impl NFTMarketplace {
         image_uri: String,
         creator: String,
         royalty_percentage: f64,
-    ) -> String {
-        let token_id = format!("nft_{}_{}", collection, self.nfts.len() + 1);
-        
+    ) -> Result<String, String> {
+        let minted_so_far = self.storage.nft_count().await?;
+        let token_id = format!("nft_{}_{}", collection, minted_so_far + 1);
+
         let nft = NFT {
             token_id: token_id.clone(),
             collection: collection.clone(),
@@ -92,184 +790,345 @@ impl NFTMarketplace {
             rarity_score: 0,
             attributes: HashMap::new(),
         };
-        
-        self.nfts.insert(token_id. clone(), nft);
-        
-        // Update collection
-        if let Some(col) = self.collections.get_mut(&collection) {
+
+        self.storage.put_nft(nft).await?;
+
+        if let Some(mut col) = self.storage.get_collection(&collection).await? {
             col.total_items += 1;
+            self.storage.put_collection(col).await?;
         }
-        
+
         println!("🎨 NFT minted: {} in collection {}", name, collection);
-        
-        token_id
+
+        Ok(token_id)
     }
-    
-    // List NFT for sale
-    pub fn list_nft(&mut self, nft_id: String, seller: String, price: u64, currency: String) -> bool {
-        // Verify ownership
-        if let Some(nft) = self. nfts.get(&nft_id) {
-            if nft.owner != seller {
-                println!("❌ Not the owner");
-                return false;
+
+    /// Submits a commitment for a future mint; rarity and attributes
+    /// aren't decided until `reveal_mint` is called at or after
+    /// `reveal_block`, so neither the minter nor the marketplace can bias
+    /// the roll after seeing it.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn commit_mint(
+        &mut self,
+        collection: String,
+        name: String,
+        description: String,
+        image_uri: String,
+        creator: String,
+        royalty_percentage: f64,
+        commitment: [u8; 32],
+        reveal_block: u64,
+    ) -> Result<String, String> {
+        let id = format!("commit_{}", hex_encode(&commitment));
+        let mut commitments = self.mint_commitments.write().await;
+        if commitments.contains_key(&id) {
+            return Err("a commitment with this hash already exists".to_string());
+        }
+
+        commitments.insert(id.clone(), MintCommitment {
+            id: id.clone(),
+            collection,
+            name,
+            description,
+            image_uri,
+            creator,
+            royalty_percentage,
+            commitment,
+            reveal_block,
+            revealed: false,
+        });
+
+        println!("🎲 mint commitment {} recorded, revealable at block {}", id, reveal_block);
+
+        Ok(id)
+    }
+
+    /// Checks `sha256(nonce || seed) == commitment` without consuming
+    /// anything - lets a buyer independently verify a reveal before
+    /// trusting it.
+    pub fn verify_commitment(nonce: &[u8], seed: &[u8], commitment: &[u8; 32]) -> bool {
+        let mut hasher = Sha256::new();
+        hasher.update(nonce);
+        hasher.update(seed);
+        let hash: [u8; 32] = hasher.finalize().into();
+        &hash == commitment
+    }
+
+    /// Folds the revealed `seed` and the not-yet-assigned `token_id` into
+    /// a rarity score and a small trait table. Deterministic given the
+    /// seed, so anyone can recompute it and confirm nothing was rigged.
+    fn derive_traits(seed: &[u8], token_id: &str) -> (u64, HashMap<String, String>) {
+        const BACKGROUNDS: [&str; 4] = ["common", "rare", "epic", "legendary"];
+        const EYES: [&str; 4] = ["normal", "laser", "sleepy", "glowing"];
+
+        let mut hasher = Sha256::new();
+        hasher.update(seed);
+        hasher.update(token_id.as_bytes());
+        let hash: [u8; 32] = hasher.finalize().into();
+
+        let rarity_score = u64::from_be_bytes(hash[0..8].try_into().unwrap()) % 10_000;
+        let mut attributes = HashMap::new();
+        attributes.insert("background".to_string(), BACKGROUNDS[hash[8] as usize % BACKGROUNDS.len()].to_string());
+        attributes.insert("eyes".to_string(), EYES[hash[9] as usize % EYES.len()].to_string());
+
+        (rarity_score, attributes)
+    }
+
+    /// Reveals `nonce`/`seed` for a prior `commit_mint`, mints the NFT
+    /// once the hash checks out and `current_block >= reveal_block`, and
+    /// assigns `rarity_score`/`attributes` via [`Self::derive_traits`].
+    /// Rejects reveals with a mismatched hash or that arrive too early.
+    pub async fn reveal_mint(&mut self, commitment_id: &str, nonce: &[u8], seed: &[u8], current_block: u64) -> Result<String, String> {
+        let pending = {
+            let mut commitments = self.mint_commitments.write().await;
+            let pending = commitments.get_mut(commitment_id).ok_or("no such mint commitment")?;
+
+            if pending.revealed {
+                return Err("commitment already revealed".to_string());
             }
-            
-            let listing = Listing {
-                nft_id: nft_id.clone(),
-                seller: seller.clone(),
-                price,
-                currency: currency.clone(),
-                listed_at: 0,  // timestamp
-                active: true,
-            };
-            
-            self.listings.insert(nft_id. clone(), listing);
-            
-            println!("📝 NFT listed: {} for {} {}", nft_id, price, currency);
-            
-            true
-        } else {
-            false
-        }
-    }
-    
-    // Buy NFT
-    pub fn buy_nft(&mut self, nft_id: String, buyer: String, payment: u64) -> bool {
-        let listing = self.listings.get_mut(&nft_id);
-        if listing.is_none() {
+            if current_block < pending.reveal_block {
+                return Err("reveal block has not been reached yet".to_string());
+            }
+            if !Self::verify_commitment(nonce, seed, &pending.commitment) {
+                return Err("seed does not match the committed hash".to_string());
+            }
+
+            pending.revealed = true;
+            pending.clone()
+        };
+
+        let minted_so_far = self.storage.nft_count().await?;
+        let token_id = format!("nft_{}_{}", pending.collection, minted_so_far + 1);
+        let (rarity_score, attributes) = Self::derive_traits(seed, &token_id);
+
+        let nft = NFT {
+            token_id: token_id.clone(),
+            collection: pending.collection.clone(),
+            name: pending.name,
+            description: pending.description,
+            image_uri: pending.image_uri,
+            owner: pending.creator.clone(),
+            creator: pending.creator,
+            royalty_percentage: pending.royalty_percentage,
+            rarity_score,
+            attributes,
+        };
+
+        self.storage.put_nft(nft).await?;
+
+        if let Some(mut col) = self.storage.get_collection(&pending.collection).await? {
+            col.total_items += 1;
+            self.storage.put_collection(col).await?;
+        }
+
+        println!("🎲 mint revealed: {} minted with rarity {}", token_id, rarity_score);
+
+        Ok(token_id)
+    }
+
+    // List NFT for sale
+    pub async fn list_nft(&mut self, nft_id: String, seller: String, price: u64, currency: String) -> bool {
+        if !self.is_denom_accepted(&currency).await {
+            println!("❌ {} is not an accepted currency", currency);
             return false;
         }
-        
-        let listing = listing.unwrap();
-        
-        if ! listing.active {
+
+        let nft = match self.storage.get_nft(&nft_id).await {
+            Ok(Some(nft)) => nft,
+            _ => return false,
+        };
+
+        if nft.owner != seller {
+            println!("❌ Not the owner");
+            return false;
+        }
+
+        let listing = Listing {
+            nft_id: nft_id.clone(),
+            seller: seller.clone(),
+            price,
+            currency: currency.clone(),
+            listed_at: 0,  // timestamp
+            active: true,
+        };
+
+        if self.storage.put_listing(listing).await.is_err() {
+            return false;
+        }
+
+        self.record_floor_price(&nft.collection, &currency, price).await;
+
+        println!("📝 NFT listed: {} for {} {}", nft_id, price, currency);
+
+        true
+    }
+
+    // Buy NFT
+    pub async fn buy_nft(&mut self, nft_id: String, buyer: String, payment: u64) -> bool {
+        let mut listing = match self.storage.get_listing(&nft_id).await {
+            Ok(Some(listing)) => listing,
+            _ => return false,
+        };
+
+        if !listing.active {
             println!("❌ Listing not active");
             return false;
         }
-        
+
+        if !self.is_denom_accepted(&listing.currency).await {
+            println!("❌ {} is no longer an accepted currency", listing.currency);
+            return false;
+        }
+
         if payment < listing.price {
             println!("❌ Insufficient payment");
             return false;
         }
-        
-        let nft = self.nfts. get_mut(&nft_id). unwrap();
-        
+
+        let mut nft = match self.storage.get_nft(&nft_id).await {
+            Ok(Some(nft)) => nft,
+            _ => return false,
+        };
+
         // Calculate fees
         let marketplace_fee_amount = (listing.price as f64 * self.marketplace_fee) as u64;
         let royalty_amount = (listing.price as f64 * nft.royalty_percentage) as u64;
         let seller_amount = listing.price - marketplace_fee_amount - royalty_amount;
-        
+
         println!("💰 Sale: {} NUSA", listing.price);
-        println! ("   → Seller: {} NUSA", seller_amount);
+        println!("   → Seller: {} NUSA", seller_amount);
         println!("   → Royalty (creator): {} NUSA", royalty_amount);
         println!("   → Marketplace fee: {} NUSA", marketplace_fee_amount);
-        
+
         // Transfer NFT
         nft.owner = buyer.clone();
-        
+        let collection = nft.collection.clone();
+        if self.storage.put_nft(nft).await.is_err() {
+            return false;
+        }
+
         // Deactivate listing
+        let seller = listing.seller.clone();
         listing.active = false;
-        
-        // Update collection floor price
-        if let Some(col) = self.collections.get_mut(&nft.collection) {
+        if self.storage.put_listing(listing.clone()).await.is_err() {
+            return false;
+        }
+
+        // Update collection volume
+        if let Ok(Some(mut col)) = self.storage.get_collection(&collection).await {
             col.total_volume += listing.price;
+            let _ = self.storage.put_collection(col).await;
         }
-        
-        println!("✅ {} bought {} from {}", buyer, nft_id, listing.seller);
-        
+        self.record_volume(&collection, &listing.currency, listing.price).await;
+
+        println!("✅ {} bought {} from {}", buyer, nft_id, seller);
+
         true
     }
-    
+
     // Create auction
-    pub fn create_auction(&mut self, nft_id: String, seller: String, starting_bid: u64, duration: u64) -> bool {
-        if let Some(nft) = self.nfts.get(&nft_id) {
-            if nft.owner != seller {
-                return false;
-            }
-            
-            let auction = Auction {
-                nft_id: nft_id.clone(),
-                seller: seller.clone(),
-                starting_bid,
-                current_bid: starting_bid,
-                highest_bidder: String::new(),
-                end_time: duration,  // timestamp
-                active: true,
-            };
-            
-            self.auctions.insert(nft_id.clone(), auction);
-            
-            println!("🔨 Auction created for {} | Starting bid: {}", nft_id, starting_bid);
-            
-            true
-        } else {
-            false
-        }
-    }
-    
-    // Place bid
-    pub fn place_bid(&mut self, nft_id: String, bidder: String, bid_amount: u64) -> bool {
-        let auction = self.auctions.get_mut(&nft_id);
-        if auction.is_none() {
+    pub async fn create_auction(&mut self, nft_id: String, seller: String, starting_bid: u64, currency: String, duration: u64) -> bool {
+        if !self.is_denom_accepted(&currency).await {
+            println!("❌ {} is not an accepted currency", currency);
+            return false;
+        }
+
+        let nft = match self.storage.get_nft(&nft_id).await {
+            Ok(Some(nft)) => nft,
+            _ => return false,
+        };
+
+        if nft.owner != seller {
+            return false;
+        }
+
+        let collection = nft.collection.clone();
+        let auction = Auction {
+            nft_id: nft_id.clone(),
+            seller: seller.clone(),
+            starting_bid,
+            current_bid: starting_bid,
+            currency: currency.clone(),
+            highest_bidder: String::new(),
+            end_time: duration,  // timestamp
+            active: true,
+        };
+
+        if self.storage.put_auction(auction).await.is_err() {
             return false;
         }
-        
-        let auction = auction.unwrap();
-        
-        if ! auction.active {
-            println! ("❌ Auction ended");
+
+        self.record_floor_price(&collection, &currency, starting_bid).await;
+
+        println!("🔨 Auction created for {} | Starting bid: {}", nft_id, starting_bid);
+
+        true
+    }
+
+    // Place bid
+    pub async fn place_bid(&mut self, nft_id: String, bidder: String, bid_amount: u64) -> bool {
+        let mut auction = match self.storage.get_auction(&nft_id).await {
+            Ok(Some(auction)) => auction,
+            _ => return false,
+        };
+
+        if !auction.active {
+            println!("❌ Auction ended");
             return false;
         }
-        
+
         if bid_amount <= auction.current_bid {
             println!("❌ Bid must be higher than current bid");
             return false;
         }
-        
+
         // Refund previous bidder (simplified)
-        if ! auction.highest_bidder.is_empty() {
+        if !auction.highest_bidder.is_empty() {
             println!("↩️ Refunding {} to {}", auction.current_bid, auction.highest_bidder);
         }
-        
+
         auction.current_bid = bid_amount;
         auction.highest_bidder = bidder.clone();
-        
+
+        if self.storage.put_auction(auction).await.is_err() {
+            return false;
+        }
+
         println!("🔨 New bid: {} NUSA by {}", bid_amount, bidder);
-        
+
         true
     }
-    
+
     // End auction
-    pub fn end_auction(&mut self, nft_id: String) -> bool {
-        let auction = self.auctions. get_mut(&nft_id);
-        if auction.is_none() {
-            return false;
-        }
-        
-        let auction = auction.unwrap();
+    pub async fn end_auction(&mut self, nft_id: String) -> bool {
+        let mut auction = match self.storage.get_auction(&nft_id).await {
+            Ok(Some(auction)) => auction,
+            _ => return false,
+        };
+
         auction.active = false;
-        
+
         if auction.highest_bidder.is_empty() {
             println!("❌ No bids received");
+            let _ = self.storage.put_auction(auction).await;
             return false;
         }
-        
-        // Transfer NFT to winner
-        if let Some(nft) = self.nfts.get_mut(&nft_id) {
-            nft.owner = auction.highest_bidder.clone();
-            
-            println!("🏆 Auction won by {} for {} NUSA", auction.highest_bidder, auction.current_bid);
-            
-            true
-        } else {
-            false
-        }
+
+        let mut nft = match self.storage.get_nft(&nft_id).await {
+            Ok(Some(nft)) => nft,
+            _ => return false,
+        };
+        nft.owner = auction.highest_bidder.clone();
+
+        println!("🏆 Auction won by {} for {} NUSA", auction.highest_bidder, auction.current_bid);
+
+        self.storage.put_nft(nft).await.is_ok() && self.storage.put_auction(auction).await.is_ok()
     }
-    
+
     // Create collection
-    pub fn create_collection(&mut self, name: String, creator: String) -> String {
-        let id = format!("col_{}", self.collections.len() + 1);
-        
+    pub async fn create_collection(&mut self, name: String, creator: String) -> Result<String, String> {
+        let id = format!("col_{}", self.storage.collection_count().await? + 1);
+
         let collection = Collection {
             id: id.clone(),
             name: name.clone(),
@@ -279,18 +1138,284 @@ impl NFTMarketplace {
             total_volume: 0,
             verified: false,
         };
-        
-        self.collections.insert(id. clone(), collection);
-        
+
+        self.storage.put_collection(collection).await?;
+
         println!("📚 Collection created: {}", name);
-        
-        id
+
+        Ok(id)
     }
-    
+
     // Get trending NFTs
-    pub fn get_trending(&self) -> Vec<String> {
+    pub async fn get_trending(&self) -> Vec<String> {
         // Sort by recent sales volume
         // (Simplified - production needs time-based sorting)
-        self.nfts.keys().take(10).cloned().collect()
+        match self.nfts().await {
+            Ok(nfts) => nfts.into_iter().take(10).map(|nft| nft.token_id).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // `MarketplaceStorage` (added so the marketplace can run against a real
+    // database or IndexedDB) means there's no owned `HashMap` left on
+    // `NFTMarketplace` to hand out `&T` borrows into, so these return owned
+    // snapshots rather than true zero-copy iterators. They still give
+    // callers the filterable, no-manual-cloning-at-the-call-site shape the
+    // old per-field clones didn't.
+
+    /// Every NFT currently known to the backing store.
+    pub async fn nfts(&self) -> Result<Vec<NFT>, String> {
+        self.storage.all_nfts().await
+    }
+
+    /// Every listing, active or not - callers that only want active ones
+    /// should filter on `Listing::active`.
+    pub async fn listings(&self) -> Result<Vec<Listing>, String> {
+        self.storage.all_listings().await
+    }
+
+    /// Auctions that haven't been settled by `end_auction` yet.
+    pub async fn active_auctions(&self) -> Result<Vec<Auction>, String> {
+        Ok(self.storage.all_auctions().await?.into_iter().filter(|a| a.active).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marketplace() -> NFTMarketplace {
+        let storage = SqlMarketplaceStorage::in_memory().expect("in-memory sqlite always opens");
+        NFTMarketplace::new(Box::new(storage), "admin".to_string())
+    }
+
+    #[tokio::test]
+    async fn mint_list_and_buy_round_trips_through_sql_storage() {
+        let mut market = marketplace();
+        market.create_collection("Genesis".to_string(), "alice".to_string()).await.unwrap();
+        let token_id = market
+            .mint_nft("col_1".to_string(), "Cool Cat".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.05)
+            .await
+            .unwrap();
+
+        assert!(market.list_nft(token_id.clone(), "alice".to_string(), 100, "NUSA".to_string()).await);
+        assert!(market.buy_nft(token_id.clone(), "bob".to_string(), 100).await);
+
+        let nft = market.storage.get_nft(&token_id).await.unwrap().unwrap();
+        assert_eq!(nft.owner, "bob");
+    }
+
+    #[tokio::test]
+    async fn htlc_redeem_with_the_right_preimage_transfers_to_taker() {
+        let mut market = marketplace();
+        let token_id = market
+            .mint_nft("col_x".to_string(), "Swap Me".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        let preimage = b"super-secret".to_vec();
+        let hashlock: [u8; 32] = Sha256::digest(&preimage).into();
+        let lock_id = market
+            .lock_htlc(token_id.clone(), "alice".to_string(), "bob".to_string(), hashlock, now_secs() + 3600)
+            .await
+            .unwrap();
+
+        assert_eq!(market.redeem_htlc(&lock_id, &preimage).await.unwrap(), preimage);
+        assert_eq!(market.storage.get_nft(&token_id).await.unwrap().unwrap().owner, "bob");
+    }
+
+    #[tokio::test]
+    async fn htlc_redeem_with_the_wrong_preimage_is_rejected() {
+        let mut market = marketplace();
+        let token_id = market
+            .mint_nft("col_x".to_string(), "Swap Me".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        let hashlock: [u8; 32] = Sha256::digest(b"correct-secret").into();
+        let lock_id = market
+            .lock_htlc(token_id.clone(), "alice".to_string(), "bob".to_string(), hashlock, now_secs() + 3600)
+            .await
+            .unwrap();
+
+        assert!(market.redeem_htlc(&lock_id, b"wrong-secret").await.is_err());
+        assert_eq!(market.storage.get_nft(&token_id).await.unwrap().unwrap().owner, format!("htlc-escrow:{}", lock_id));
+    }
+
+    #[tokio::test]
+    async fn htlc_refund_before_expiry_is_rejected() {
+        let mut market = marketplace();
+        let token_id = market
+            .mint_nft("col_x".to_string(), "Swap Me".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        let hashlock: [u8; 32] = Sha256::digest(b"secret").into();
+        let lock_id = market
+            .lock_htlc(token_id.clone(), "alice".to_string(), "bob".to_string(), hashlock, now_secs() + 3600)
+            .await
+            .unwrap();
+
+        assert!(market.refund_htlc(&lock_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn htlc_refund_after_expiry_returns_the_nft_to_maker() {
+        let mut market = marketplace();
+        let token_id = market
+            .mint_nft("col_x".to_string(), "Swap Me".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        let hashlock: [u8; 32] = Sha256::digest(b"secret").into();
+        // Timelock of 0 is already in the past relative to any real clock.
+        let lock_id = market
+            .lock_htlc(token_id.clone(), "alice".to_string(), "bob".to_string(), hashlock, 0)
+            .await
+            .unwrap();
+
+        assert!(market.refund_htlc(&lock_id).await.is_ok());
+        assert_eq!(market.storage.get_nft(&token_id).await.unwrap().unwrap().owner, "alice");
+    }
+
+    #[tokio::test]
+    async fn reveal_mint_with_the_right_seed_assigns_deterministic_traits() {
+        let mut market = marketplace();
+        market.create_collection("Genesis".to_string(), "alice".to_string()).await.unwrap();
+
+        let nonce = b"nonce".to_vec();
+        let seed = b"future-seed".to_vec();
+        let mut hasher = Sha256::new();
+        hasher.update(&nonce);
+        hasher.update(&seed);
+        let commitment: [u8; 32] = hasher.finalize().into();
+
+        let commit_id = market
+            .commit_mint("col_1".to_string(), "Mystery".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.05, commitment, 100)
+            .await
+            .unwrap();
+
+        let token_id = market.reveal_mint(&commit_id, &nonce, &seed, 100).await.unwrap();
+        let nft = market.storage.get_nft(&token_id).await.unwrap().unwrap();
+        assert!(nft.rarity_score < 10_000);
+        assert!(nft.attributes.contains_key("background"));
+    }
+
+    #[tokio::test]
+    async fn reveal_mint_before_the_reveal_block_is_rejected() {
+        let mut market = marketplace();
+        market.create_collection("Genesis".to_string(), "alice".to_string()).await.unwrap();
+
+        let nonce = b"nonce".to_vec();
+        let seed = b"future-seed".to_vec();
+        let mut hasher = Sha256::new();
+        hasher.update(&nonce);
+        hasher.update(&seed);
+        let commitment: [u8; 32] = hasher.finalize().into();
+
+        let commit_id = market
+            .commit_mint("col_1".to_string(), "Mystery".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.05, commitment, 100)
+            .await
+            .unwrap();
+
+        assert!(market.reveal_mint(&commit_id, &nonce, &seed, 99).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reveal_mint_with_a_mismatched_seed_is_rejected() {
+        let mut market = marketplace();
+        market.create_collection("Genesis".to_string(), "alice".to_string()).await.unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"nonce");
+        hasher.update(b"real-seed");
+        let commitment: [u8; 32] = hasher.finalize().into();
+
+        let commit_id = market
+            .commit_mint("col_1".to_string(), "Mystery".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.05, commitment, 0)
+            .await
+            .unwrap();
+
+        assert!(market.reveal_mint(&commit_id, b"nonce", b"wrong-seed", 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn listing_in_an_unregistered_denom_is_rejected() {
+        let mut market = marketplace();
+        market.create_collection("Genesis".to_string(), "alice".to_string()).await.unwrap();
+        let token_id = market
+            .mint_nft("col_1".to_string(), "Cool Cat".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        assert!(!market.list_nft(token_id, "alice".to_string(), 100, "NUSD".to_string()).await);
+    }
+
+    #[tokio::test]
+    async fn buying_after_a_denom_is_disabled_is_rejected() {
+        let mut market = marketplace();
+        market.create_collection("Genesis".to_string(), "alice".to_string()).await.unwrap();
+        let token_id = market
+            .mint_nft("col_1".to_string(), "Cool Cat".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        assert!(market.list_nft(token_id.clone(), "alice".to_string(), 100, "NUSA".to_string()).await);
+        market.admin_remove_denom("admin", "NUSA").await.unwrap();
+
+        assert!(!market.buy_nft(token_id, "bob".to_string(), 100).await);
+    }
+
+    #[tokio::test]
+    async fn non_admin_cannot_register_a_denom_or_change_the_fee() {
+        let mut market = marketplace();
+        assert!(market.admin_add_denom("mallory", "NUSD".to_string(), 6).await.is_err());
+        assert!(market.admin_set_fee("mallory", 0.1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn admin_added_denom_can_be_listed_in_and_tracks_floor_price() {
+        let mut market = marketplace();
+        market.admin_add_denom("admin", "NUSD".to_string(), 6).await.unwrap();
+        market.create_collection("Genesis".to_string(), "alice".to_string()).await.unwrap();
+        let cheap = market
+            .mint_nft("col_1".to_string(), "Cheap".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+        let pricey = market
+            .mint_nft("col_1".to_string(), "Pricey".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        assert!(market.list_nft(pricey, "alice".to_string(), 500, "NUSD".to_string()).await);
+        assert!(market.list_nft(cheap, "alice".to_string(), 50, "NUSD".to_string()).await);
+
+        assert_eq!(market.denom_stats("col_1", "NUSD").await, (50, 0));
+    }
+
+    #[tokio::test]
+    async fn listing_by_non_owner_is_rejected() {
+        let mut market = marketplace();
+        let token_id = market
+            .mint_nft("col_x".to_string(), "Fake".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        assert!(!market.list_nft(token_id, "mallory".to_string(), 100, "NUSA".to_string()).await);
+    }
+
+    #[tokio::test]
+    async fn auction_without_bids_does_not_transfer_ownership() {
+        let mut market = marketplace();
+        let token_id = market
+            .mint_nft("col_x".to_string(), "Piece".to_string(), "desc".to_string(), "ipfs://img".to_string(), "alice".to_string(), 0.0)
+            .await
+            .unwrap();
+
+        assert!(market.create_auction(token_id.clone(), "alice".to_string(), 10, "NUSA".to_string(), 1000).await);
+        assert!(!market.end_auction(token_id.clone()).await);
+
+        let nft = market.storage.get_nft(&token_id).await.unwrap().unwrap();
+        assert_eq!(nft.owner, "alice");
     }
 }
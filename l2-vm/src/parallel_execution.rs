@@ -1,17 +1,170 @@
 // Parallel Smart Contract Execution Engine (Sealevel-style)
 // Features: GPU acceleration, account-based concurrency, conflict detection
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+/// How many batch "slots" of transaction hashes the status cache keeps
+/// before evicting the oldest - bounds its memory regardless of how long
+/// the engine runs.
+const DEFAULT_STATUS_CACHE_WINDOW: usize = 64;
+
+/// How many recent fee samples the prioritization fee cache keeps per
+/// account (one sample per recorded transaction).
+const DEFAULT_FEE_CACHE_WINDOW: usize = 64;
+
+/// Compute-unit cost charged just for verifying a transaction's signature,
+/// mirroring Solana's fixed per-signature base cost.
+const BASE_SIGNATURE_COST: u64 = 1_000;
+/// Compute-unit cost per account the transaction write-locks.
+const WRITE_LOCK_COST: u64 = 200;
+/// Compute-unit cost per byte of calldata.
+const DATA_BYTE_COST: u64 = 10;
+
+/// Default ceiling on accumulated cost per parallel group before the rest
+/// of the group's transactions spill into the next batch.
+const DEFAULT_BLOCK_COST_LIMIT: u64 = 1_000_000;
+
+/// Cost to execute `tx`: base signature verification, plus
+/// `WRITE_LOCK_COST` per write lock held, plus `DATA_BYTE_COST` per byte of
+/// calldata.
+pub fn tx_cost(tx: &Transaction) -> u64 {
+    BASE_SIGNATURE_COST
+        + tx.writes.len() as u64 * WRITE_LOCK_COST
+        + tx.data.len() as u64 * DATA_BYTE_COST
+}
+
 pub struct ParallelExecutionEngine {
     pub threads: usize,
     pub gpu_enabled: bool,
     pub executed_txs: Arc<Mutex<Vec<Transaction>>>,
     pub conflict_detector: ConflictDetector,
     pub account_locks: Arc<Mutex<HashMap<String, bool>>>,
+    pub status_cache: Arc<Mutex<StatusCache>>,
+    pub fee_cache: Arc<Mutex<PrioritizationFeeCache>>,
+    /// Compute-unit ceiling per parallel group; transactions beyond it
+    /// spill into the next batch instead of being dropped.
+    pub block_cost_limit: u64,
     pub throughput_tps: f64,
+    total_cost_consumed: Arc<Mutex<u64>>,
+    total_fees_collected: Arc<Mutex<u64>>,
+    current_slot: u64,
+}
+
+/// Tracks the minimum priority fee that has landed for each written
+/// account over a rolling window of its most recent fee samples, so callers can estimate
+/// what fee a transaction needs to pay to land against a hot account.
+pub struct PrioritizationFeeCache {
+    window_size: usize,
+    per_account: HashMap<String, VecDeque<(u64, u64)>>,
+}
+
+impl PrioritizationFeeCache {
+    pub fn new(window_size: usize) -> Self {
+        PrioritizationFeeCache {
+            window_size,
+            per_account: HashMap::new(),
+        }
+    }
+
+    /// Record that a transaction touching `accounts` landed in `slot`
+    /// paying `fee`.
+    pub fn record(&mut self, accounts: &[String], slot: u64, fee: u64) {
+        for account in accounts {
+            let samples = self
+                .per_account
+                .entry(account.clone())
+                .or_insert_with(VecDeque::new);
+            samples.push_back((slot, fee));
+            while samples.len() > self.window_size {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// Minimum fee observed for `account` within the rolling window, or
+    /// `None` if nothing has landed for it yet.
+    pub fn min_fee(&self, account: &str) -> Option<u64> {
+        self.per_account
+            .get(account)
+            .and_then(|samples| samples.iter().map(|(_, fee)| *fee).min())
+    }
+}
+
+/// Deduplicates transactions by a `blake3` hash of their canonical message
+/// bytes (from/to/value/data), so replays or duplicate submissions across
+/// batches short-circuit before expensive dependency analysis runs. Bounded
+/// to a rolling window of recent batch slots.
+pub struct StatusCache {
+    window_size: usize,
+    entries: HashMap<[u8; 32], u64>,
+    slots: VecDeque<(u64, Vec<[u8; 32]>)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl StatusCache {
+    pub fn new(window_size: usize) -> Self {
+        StatusCache {
+            window_size,
+            entries: HashMap::new(),
+            slots: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn message_hash(tx: &Transaction) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(tx.from.as_bytes());
+        hasher.update(tx.to.as_bytes());
+        hasher.update(&tx.value.to_le_bytes());
+        hasher.update(&tx.data);
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Whether `tx`'s message hash is already present in the window.
+    /// Records a hit or miss either way; does not insert - call `record`
+    /// once the transaction has actually executed successfully.
+    pub fn contains(&mut self, tx: &Transaction) -> bool {
+        let hash = Self::message_hash(tx);
+        let seen = self.entries.contains_key(&hash);
+        if seen {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        seen
+    }
+
+    /// Record `tx` as seen in `slot`, evicting the oldest slot once the
+    /// rolling window is exceeded.
+    pub fn record(&mut self, tx: &Transaction, slot: u64) {
+        let hash = Self::message_hash(tx);
+        self.entries.insert(hash, slot);
+
+        match self.slots.back_mut() {
+            Some((s, hashes)) if *s == slot => hashes.push(hash),
+            _ => self.slots.push_back((slot, vec![hash])),
+        }
+
+        while self.slots.len() > self.window_size {
+            if let Some((_, hashes)) = self.slots.pop_front() {
+                for h in hashes {
+                    self.entries.remove(&h);
+                }
+            }
+        }
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +177,9 @@ pub struct Transaction {
     pub reads: Vec<String>,   // Accounts read
     pub writes: Vec<String>,  // Accounts written
     pub executed: bool,
+    /// Optional fee-market bid, in the same compute-unit terms as `tx_cost`.
+    /// Higher-paying transactions are scheduled first within a group.
+    pub priority_fee: u64,
 }
 
 pub struct ConflictDetector {
@@ -37,6 +193,22 @@ pub struct ExecutionBatch {
     pub total_txs: usize,
 }
 
+/// Synthetic write-lock contention patterns for benchmarking how
+/// `group_independent_txs` degrades from fully parallel to fully
+/// sequential, mirroring the conflict shapes real schedulers are stressed
+/// against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteLockContention {
+    /// No two transactions touch the same account - fully parallel.
+    None,
+    /// Transactions only contend with others in the same batch-sized
+    /// window, not the whole workload.
+    SameBatchOnly,
+    /// Every transaction write-locks one shared hot account - fully
+    /// sequential.
+    Full,
+}
+
 impl ParallelExecutionEngine {
     pub fn new(threads: usize, gpu_enabled: bool) -> Self {
         Self {
@@ -45,7 +217,13 @@ impl ParallelExecutionEngine {
             executed_txs: Arc::new(Mutex::new(Vec::new())),
             conflict_detector: ConflictDetector::new(),
             account_locks: Arc::new(Mutex::new(HashMap::new())),
+            status_cache: Arc::new(Mutex::new(StatusCache::new(DEFAULT_STATUS_CACHE_WINDOW))),
+            fee_cache: Arc::new(Mutex::new(PrioritizationFeeCache::new(DEFAULT_FEE_CACHE_WINDOW))),
+            block_cost_limit: DEFAULT_BLOCK_COST_LIMIT,
             throughput_tps: 0.0,
+            total_cost_consumed: Arc::new(Mutex::new(0)),
+            total_fees_collected: Arc::new(Mutex::new(0)),
+            current_slot: 0,
         }
     }
 
@@ -67,7 +245,7 @@ impl ParallelExecutionEngine {
             println!("  Group {}: {} txs", group_id, group.len());
 
             // Execute group (all txs are independent)
-            let executed = self.execute_group(group. clone())?;
+            let executed = self.execute_group(group. clone(), batch.batch_id)?;
             total_executed += executed;
         }
 
@@ -87,19 +265,105 @@ impl ParallelExecutionEngine {
 
     // Prepare execution batch with dependency analysis
     fn prepare_execution_batch(&mut self, txs: Vec<Transaction>) -> Result<ExecutionBatch, String> {
+        self.current_slot += 1;
+        let slot = self.current_slot;
+
+        // 0. Drop anything already in the status cache's window before
+        // dependency analysis runs - cheaper than conflict detection, and
+        // protects against replays/duplicate submissions across batches.
+        // `StatusCache::contains` only checks slots already `record`ed
+        // (i.e. past batches), so two identical messages submitted in
+        // this same batch would both miss it; track hashes seen so far
+        // within this batch too, so intra-batch duplicates are dropped
+        // before grouping.
+        let fresh_txs = {
+            let mut cache = self.status_cache.lock().unwrap();
+            let mut seen_this_batch = HashSet::new();
+            txs.into_iter()
+                .filter(|tx| {
+                    let hash = StatusCache::message_hash(tx);
+                    if cache.contains(tx) || !seen_this_batch.insert(hash) {
+                        println!("⏭️  Dropping duplicate tx {} (status cache hit)", tx.hash);
+                        false
+                    } else {
+                        true
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
         // 1. Analyze account dependencies
-        self.analyze_dependencies(&txs);
+        self.analyze_dependencies(&fresh_txs);
 
         // 2. Group independent transactions
-        let parallel_groups = self.group_independent_txs(txs);
+        let parallel_groups = self.group_independent_txs(fresh_txs);
+        let total_txs = parallel_groups.iter().map(|g| g.len()).sum();
 
         Ok(ExecutionBatch {
-            batch_id: 1,
+            batch_id: slot,
             parallel_groups,
-            total_txs: parallel_groups.iter().map(|g| g. len()).sum(),
+            total_txs,
         })
     }
 
+    /// Generate a synthetic workload of `n` transactions under the given
+    /// contention mode, for benchmarking scheduler quality under
+    /// adversarial conflict patterns.
+    pub fn generate_workload(contention: WriteLockContention, n: usize) -> Vec<Transaction> {
+        const SAME_BATCH_WINDOW: usize = 8;
+
+        (0..n)
+            .map(|i| {
+                let write_account = match contention {
+                    WriteLockContention::None => format!("account_{}", i),
+                    WriteLockContention::SameBatchOnly => format!("account_{}", i / SAME_BATCH_WINDOW),
+                    WriteLockContention::Full => "hot_account".to_string(),
+                };
+
+                Transaction {
+                    hash: format!("tx_{}", i),
+                    from: format!("sender_{}", i),
+                    to: write_account.clone(),
+                    value: 1,
+                    data: vec![],
+                    reads: vec![format!("sender_{}", i)],
+                    writes: vec![write_account],
+                    executed: false,
+                    priority_fee: 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Run `execute_parallel` once per contention mode and report
+    /// throughput/conflicts for each, so the Sealevel-style parallelism
+    /// claims are directly measurable rather than assumed.
+    pub fn benchmark_contention_modes(n: usize) -> Vec<(WriteLockContention, ExecutionResult)> {
+        let modes = [
+            WriteLockContention::None,
+            WriteLockContention::SameBatchOnly,
+            WriteLockContention::Full,
+        ];
+
+        modes
+            .iter()
+            .map(|&mode| {
+                let mut engine = ParallelExecutionEngine::new(8, false);
+                let workload = Self::generate_workload(mode, n);
+                let result = engine
+                    .execute_parallel(workload)
+                    .expect("synthetic benchmark workload should always execute");
+
+                println!(
+                    "📈 Contention {:?}: {:.0} TPS, {} conflicts detected",
+                    mode, result.throughput_tps, result.conflicts_detected
+                );
+
+                (mode, result)
+            })
+            .collect()
+    }
+
     // Analyze transaction dependencies
     fn analyze_dependencies(&mut self, txs: &[Transaction]) {
         for tx in txs {
@@ -192,7 +456,25 @@ impl ParallelExecutionEngine {
             }
 
             if !independent_group.is_empty() {
-                groups.push(independent_group);
+                // Fee-market ordering: highest priority fee first, capped
+                // by accumulated compute cost. Transactions that would push
+                // the group over `block_cost_limit` spill back into
+                // `remaining` for the next batch rather than being dropped.
+                independent_group.sort_by(|a, b| b.priority_fee.cmp(&a.priority_fee));
+
+                let mut accepted = Vec::new();
+                let mut accumulated_cost = 0u64;
+                for tx in independent_group {
+                    let cost = tx_cost(&tx);
+                    if accumulated_cost + cost > self.block_cost_limit && !accepted.is_empty() {
+                        remaining.push(tx);
+                        continue;
+                    }
+                    accumulated_cost += cost;
+                    accepted.push(tx);
+                }
+
+                groups.push(accepted);
             } else {
                 // No more independent groups, force sequential
                 break;
@@ -210,7 +492,7 @@ impl ParallelExecutionEngine {
     }
 
     // Execute a group of independent transactions
-    fn execute_group(&self, txs: Vec<Transaction>) -> Result<usize, String> {
+    fn execute_group(&self, txs: Vec<Transaction>, slot: u64) -> Result<usize, String> {
         let executed_count = Arc::new(Mutex::new(0));
         let mut handles = vec![];
 
@@ -222,12 +504,23 @@ impl ParallelExecutionEngine {
             let executed_txs = Arc::clone(&self.executed_txs);
             let executed_count = Arc::clone(&executed_count);
             let account_locks = Arc::clone(&self.account_locks);
+            let status_cache = Arc::clone(&self.status_cache);
+            let fee_cache = Arc::clone(&self.fee_cache);
+            let total_cost_consumed = Arc::clone(&self.total_cost_consumed);
+            let total_fees_collected = Arc::clone(&self.total_fees_collected);
 
             let handle = thread::spawn(move || {
                 for tx in chunk_txs {
                     // Execute transaction
                     Self::execute_single(&tx, &account_locks);
 
+                    // Only record the hash in the status cache once
+                    // execution actually succeeded
+                    status_cache.lock().unwrap().record(&tx, slot);
+                    fee_cache.lock().unwrap().record(&tx.writes, slot, tx.priority_fee);
+                    *total_cost_consumed.lock().unwrap() += tx_cost(&tx);
+                    *total_fees_collected.lock().unwrap() += tx.priority_fee;
+
                     // Record execution
                     executed_txs.lock().unwrap().push(tx);
 
@@ -294,18 +587,29 @@ impl ParallelExecutionEngine {
 
     // Get execution stats
     pub fn get_stats(&self) -> String {
+        let cache = self.status_cache.lock().unwrap();
         format!(
             "Parallel Execution Stats:\n\
              Threads: {}\n\
              GPU Enabled: {}\n\
              Throughput: {:.0} TPS\n\
              Total Executed: {}\n\
-             Conflicts Detected: {}",
+             Conflicts Detected: {}\n\
+             Status Cache Hits: {}\n\
+             Status Cache Misses: {}\n\
+             Block Cost Limit: {}\n\
+             Total Cost Consumed: {}\n\
+             Total Fees Collected: {}",
             self.threads,
             self.gpu_enabled,
             self.throughput_tps,
             self.executed_txs.lock().unwrap().len(),
-            self.conflict_detector.conflicts_detected
+            self.conflict_detector.conflicts_detected,
+            cache.hits(),
+            cache.misses(),
+            self.block_cost_limit,
+            *self.total_cost_consumed.lock().unwrap(),
+            *self.total_fees_collected.lock().unwrap(),
         )
     }
 }
@@ -351,6 +655,7 @@ mod tests {
                 reads: vec!["alice".to_string()],
                 writes: vec!["bob".to_string()],
                 executed: false,
+                priority_fee: 0,
             },
             Transaction {
                 hash: "tx2".to_string(),
@@ -361,10 +666,185 @@ mod tests {
                 reads: vec!["charlie".to_string()],
                 writes: vec!["dave".to_string()],
                 executed: false,
+                priority_fee: 0,
             },
         ];
 
         let result = engine. execute_parallel(txs);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn benchmark_modes_execute_every_tx() {
+        let results = ParallelExecutionEngine::benchmark_contention_modes(32);
+        assert_eq!(results.len(), 3);
+        for (mode, result) in &results {
+            assert_eq!(result.total_txs, 32, "{:?} mode should still execute every tx", mode);
+        }
+    }
+
+    #[test]
+    fn grouping_never_puts_two_writers_of_the_same_account_together() {
+        for mode in [
+            WriteLockContention::None,
+            WriteLockContention::SameBatchOnly,
+            WriteLockContention::Full,
+        ] {
+            let mut engine = ParallelExecutionEngine::new(4, false);
+            let txs = ParallelExecutionEngine::generate_workload(mode, 16);
+            let batch = engine.prepare_execution_batch(txs).unwrap();
+
+            for group in &batch.parallel_groups {
+                let mut seen = HashSet::new();
+                for tx in group {
+                    for account in tx.reads.iter().chain(tx.writes.iter()) {
+                        assert!(
+                            seen.insert(account.clone()),
+                            "group contains two txs touching {} under {:?}",
+                            account,
+                            mode
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn full_contention_forces_fully_sequential_groups() {
+        let mut engine = ParallelExecutionEngine::new(4, false);
+        let txs = ParallelExecutionEngine::generate_workload(WriteLockContention::Full, 10);
+        let batch = engine.prepare_execution_batch(txs).unwrap();
+        assert_eq!(batch.parallel_groups.len(), 10);
+    }
+
+    #[test]
+    fn no_contention_fits_in_a_single_group() {
+        let mut engine = ParallelExecutionEngine::new(4, false);
+        let txs = ParallelExecutionEngine::generate_workload(WriteLockContention::None, 10);
+        let batch = engine.prepare_execution_batch(txs).unwrap();
+        assert_eq!(batch.parallel_groups.len(), 1);
+    }
+
+    fn sample_tx(hash: &str) -> Transaction {
+        Transaction {
+            hash: hash.to_string(),
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            value: 100,
+            data: vec![],
+            reads: vec!["alice".to_string()],
+            writes: vec!["bob".to_string()],
+            executed: false,
+            priority_fee: 0,
+        }
+    }
+
+    #[test]
+    fn status_cache_drops_duplicate_message_within_a_batch() {
+        let mut engine = ParallelExecutionEngine::new(4, false);
+        // Same from/to/value/data as sample_tx("tx1") but a different hash -
+        // the status cache keys on message content, not the tx hash.
+        let dup = Transaction { hash: "tx1-resubmitted".to_string(), ..sample_tx("tx1") };
+
+        let batch = engine.prepare_execution_batch(vec![sample_tx("tx1"), dup]).unwrap();
+        assert_eq!(batch.total_txs, 1);
+    }
+
+    #[test]
+    fn status_cache_drops_replay_across_batches() {
+        let mut engine = ParallelExecutionEngine::new(4, false);
+        let result = engine.execute_parallel(vec![sample_tx("tx1")]).unwrap();
+        assert_eq!(result.total_txs, 1);
+
+        // Resubmitting the identical transaction in a later batch should be
+        // recognized as a replay and dropped before execution.
+        let batch = engine.prepare_execution_batch(vec![sample_tx("tx1")]).unwrap();
+        assert_eq!(batch.total_txs, 0);
+        assert!(engine.status_cache.lock().unwrap().hits() >= 1);
+    }
+
+    fn priced_tx(hash: &str, account: &str, fee: u64) -> Transaction {
+        Transaction {
+            hash: hash.to_string(),
+            from: format!("{}-sender", account),
+            to: account.to_string(),
+            value: 1,
+            data: vec![],
+            reads: vec![format!("{}-sender", account)],
+            writes: vec![account.to_string()],
+            executed: false,
+            priority_fee: fee,
+        }
+    }
+
+    #[test]
+    fn group_independent_txs_orders_by_descending_priority_fee() {
+        let engine = ParallelExecutionEngine::new(4, false);
+        let txs = vec![
+            priced_tx("low", "a", 10),
+            priced_tx("high", "b", 1000),
+            priced_tx("mid", "c", 100),
+        ];
+
+        let groups = engine.group_independent_txs(txs);
+        assert_eq!(groups.len(), 1);
+        let fees: Vec<u64> = groups[0].iter().map(|tx| tx.priority_fee).collect();
+        assert_eq!(fees, vec![1000, 100, 10]);
+    }
+
+    #[test]
+    fn group_independent_txs_spills_over_block_cost_limit() {
+        let mut engine = ParallelExecutionEngine::new(4, false);
+        let one_tx_cost = tx_cost(&priced_tx("probe", "a", 0));
+        // Exactly enough room for two of the three conflict-free txs.
+        engine.block_cost_limit = one_tx_cost * 2;
+
+        let txs = vec![
+            priced_tx("low", "a", 10),
+            priced_tx("high", "b", 1000),
+            priced_tx("mid", "c", 100),
+        ];
+
+        let groups = engine.group_independent_txs(txs);
+        assert_eq!(groups.len(), 2, "spillover should form a second batch");
+        assert_eq!(groups[0].len(), 2);
+        let first_batch_fees: Vec<u64> = groups[0].iter().map(|tx| tx.priority_fee).collect();
+        assert_eq!(first_batch_fees, vec![1000, 100], "highest fees land in the first batch");
+        assert_eq!(groups[1][0].priority_fee, 10);
+    }
+
+    #[test]
+    fn group_independent_txs_always_admits_at_least_one_tx_even_over_limit() {
+        let mut engine = ParallelExecutionEngine::new(4, false);
+        engine.block_cost_limit = 1; // smaller than any single tx's cost
+
+        let groups = engine.group_independent_txs(vec![priced_tx("only", "a", 5)]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 1);
+    }
+
+    #[test]
+    fn fee_cache_tracks_min_fee_per_account_within_window() {
+        let mut cache = PrioritizationFeeCache::new(2);
+        cache.record(&["acct".to_string()], 1, 50);
+        cache.record(&["acct".to_string()], 2, 10);
+        assert_eq!(cache.min_fee("acct"), Some(10));
+
+        // Window is 2 slots - the slot-1 sample should age out here.
+        cache.record(&["acct".to_string()], 3, 30);
+        assert_eq!(cache.min_fee("acct"), Some(10));
+        cache.record(&["acct".to_string()], 4, 30);
+        assert_eq!(cache.min_fee("acct"), Some(30));
+    }
+
+    #[test]
+    fn get_stats_reports_cost_and_fees_after_execution() {
+        let mut engine = ParallelExecutionEngine::new(4, false);
+        engine.execute_parallel(vec![priced_tx("tx1", "a", 500)]).unwrap();
+
+        let stats = engine.get_stats();
+        assert!(stats.contains("Total Cost Consumed"));
+        assert!(stats.contains("Total Fees Collected: 500"));
+    }
 }
@@ -3,6 +3,25 @@
 
 use std::collections::HashMap;
 
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Byte-length comparison that doesn't short-circuit on the first mismatch,
+/// so a viewing-key check can't leak how many leading bytes an attacker
+/// guessed right via timing.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
 pub struct PrivateContractVM {
     pub contracts: HashMap<String, PrivateContract>,
     pub encrypted_state: HashMap<String, EncryptedData>,
@@ -14,7 +33,7 @@ pub struct PrivateContractVM {
 pub struct PrivateContract {
     pub address: String,
     pub owner: String,
-    pub encrypted_bytecode: Vec<u8>,
+    pub encrypted_bytecode: EncryptedData,
     pub encrypted_state: HashMap<String, EncryptedData>,
     pub access_control: AccessControl,
     pub deployed_at: u64,
@@ -53,6 +72,36 @@ pub struct ExecutionProof {
     pub function_hash: String,
     pub proof: Vec<u8>,  // ZK proof of correct execution
     pub timestamp: u64,
+    /// Notary signatures that reached consensus on this proof's output
+    /// hash, kept alongside it so a later public on-chain step can verify
+    /// agreement without learning the inputs or outputs themselves.
+    pub signatures: Vec<ValidatorSignature>,
+}
+
+/// One validator's attestation that independently re-executing the call
+/// produced `output_hash`, modeled on OpenEthereum's private-transaction
+/// notary flow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorSignature {
+    pub validator: String,
+    pub output_hash: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// Picks the output hash a `threshold` of validators agree on, or errors on
+/// divergence - no partial/majority-of-whatever-happened-to-match result is
+/// ever committed.
+fn select_agreed_hash(signatures: &[ValidatorSignature], threshold: usize) -> Result<Vec<u8>, String> {
+    let mut counts: HashMap<Vec<u8>, usize> = HashMap::new();
+    for sig in signatures {
+        *counts.entry(sig.output_hash.clone()).or_insert(0) += 1;
+    }
+
+    counts
+        .into_iter()
+        .find(|(_, count)| *count >= threshold)
+        .map(|(hash, _)| hash)
+        .ok_or_else(|| "validators did not reach consensus on execution output".to_string())
 }
 
 impl PrivateContractVM {
@@ -77,7 +126,7 @@ impl PrivateContractVM {
 
         // Encrypt bytecode
         let encryption_key = self.key_manager.generate_key(&address);
-        let encrypted_bytecode = self.encrypt_data(&bytecode, &encryption_key);
+        let encrypted_bytecode = self.encrypt_data(&bytecode, &encryption_key, &address);
 
         let contract = PrivateContract {
             address: address.clone(),
@@ -99,7 +148,13 @@ impl PrivateContractVM {
         Ok(address)
     }
 
-    // Execute private function call
+    // Execute private function call. The designated validators (the
+    // contract's `allowed_callers`, or just its owner if none were named)
+    // each re-execute the call on the inputs and bytecode decrypted once
+    // here, and sign the resulting output hash; the execution proof is only
+    // recorded and the encrypted result only returned once a threshold of
+    // them agree, mirroring OpenEthereum's private-transaction notary flow.
+    // This call does not write the contract's `encrypted_state`.
     pub fn call_private_function(
         &mut self,
         contract_address: &str,
@@ -124,24 +179,43 @@ impl PrivateContractVM {
         let key = self.key_manager.get_key(contract_address)? ;
         let bytecode = self.decrypt_data(&contract.encrypted_bytecode, &key)?;
 
-        // 4.  Execute function (in secure environment)
-        let result = self.execute_private(&bytecode, function_name, &decrypted_inputs)?;
+        // 4. Each designated validator independently re-executes and signs
+        // the output hash it got.
+        let validators = if contract.access_control.allowed_callers.is_empty() {
+            vec![contract.owner.clone()]
+        } else {
+            contract.access_control.allowed_callers.clone()
+        };
+        let threshold = if validators.len() == 1 { 1 } else { validators.len() / 2 + 1 };
+
+        let mut signatures = Vec::new();
+        let mut result = None;
+        for validator in &validators {
+            let candidate_result = self.execute_private(&bytecode, function_name, &decrypted_inputs)?;
+            let output_hash = Self::hash_bytes(&candidate_result);
+            signatures.push(self.sign_execution(validator, &output_hash));
+            if result.is_none() {
+                result = Some(candidate_result);
+            }
+        }
+        let result = result.ok_or("no validators available to execute this call")?;
+
+        // 5. Only commit once a threshold of validators agree on the same
+        // output hash - divergence aborts instead of trusting a minority.
+        let agreed_hash = select_agreed_hash(&signatures, threshold)?;
 
-        // 5. Encrypt result
-        let encrypted_result = self. encrypt_data(&result, &key);
+        // 6. Encrypt result
+        let encrypted_result = self.encrypt_data(&result, &key, contract_address);
 
-        // 6. Generate ZK proof of correct execution
-        let proof = self. generate_execution_proof(contract_address, function_name, &result);
+        // 7. Generate ZK proof of correct execution, carrying the notary
+        // signatures so consensus can be checked later without revealing
+        // the private inputs/outputs.
+        let proof = self.generate_execution_proof(contract_address, function_name, &agreed_hash, signatures);
         self.execution_proofs.push(proof);
 
-        println!("✅ Private execution complete (result encrypted)");
+        println!("✅ Private execution complete ({}/{} validators agreed)", threshold, validators.len());
 
-        Ok(EncryptedData {
-            ciphertext: encrypted_result,
-            encryption_key_id: contract_address.to_string(),
-            nonce: vec![0u8; 12],
-            authenticated: true,
-        })
+        Ok(encrypted_result)
     }
 
     // Read private state (with viewing key)
@@ -157,19 +231,33 @@ impl PrivateContractVM {
         let encrypted_value = contract.encrypted_state.get(state_key)
             .ok_or("State key not found")?;
 
-        // Verify viewing key
+        // Verify viewing key (derived from the contract's encryption key,
+        // compared in constant time - this is the selective-disclosure gate)
         if !self. key_manager.verify_viewing_key(contract_address, viewing_key) {
             return Err("Invalid viewing key".to_string());
         }
 
-        // Decrypt state
-        let decrypted = self.decrypt_data(&encrypted_value. ciphertext, viewing_key)?;
+        // Decrypt state with the contract's own encryption key; the viewing
+        // key only gates access above, it is never used as the AEAD key.
+        let key = self.key_manager.get_key(contract_address)?;
+        let decrypted = self.decrypt_data(encrypted_value, &key)?;
 
         println!("👁️ Private state read: {} (with viewing key)", state_key);
 
         Ok(decrypted)
     }
 
+    // Issue a viewing key for a contract's owner/allowed callers to hand to
+    // a selective-disclosure viewer, who can then pass it to
+    // `read_private_state`.
+    pub fn issue_viewing_key(&self, contract_address: &str, caller: &str) -> Result<Vec<u8>, String> {
+        let contract = self.contracts.get(contract_address).ok_or("Contract not found")?;
+        if !self.check_access(contract, caller) {
+            return Err("Access denied".to_string());
+        }
+        self.key_manager.derive_viewing_key(contract_address)
+    }
+
     // Write private state
     pub fn write_private_state(
         &mut self,
@@ -188,14 +276,7 @@ impl PrivateContractVM {
 
         // Encrypt value
         let key = self.key_manager.get_key(contract_address)?;
-        let encrypted_value = self.encrypt_data(&value, &key);
-
-        let encrypted_data = EncryptedData {
-            ciphertext: encrypted_value,
-            encryption_key_id: contract_address.to_string(),
-            nonce: vec![0u8; 12],
-            authenticated: true,
-        };
+        let encrypted_data = self.encrypt_data(&value, &key, contract_address);
 
         contract.encrypted_state. insert(state_key.clone(), encrypted_data);
 
@@ -215,7 +296,7 @@ impl PrivateContractVM {
 
         for input in inputs {
             let key = self.key_manager.get_key(&input.encryption_key_id)? ;
-            let plaintext = self.decrypt_data(&input.ciphertext, &key)?;
+            let plaintext = self.decrypt_data(input, &key)?;
             decrypted.push(plaintext);
         }
 
@@ -246,43 +327,73 @@ impl PrivateContractVM {
         &self,
         contract_address: &str,
         function_name: &str,
-        result: &[u8],
+        agreed_output_hash: &[u8],
+        signatures: Vec<ValidatorSignature>,
     ) -> ExecutionProof {
         // Generate ZK proof that execution was correct
         // Without revealing inputs, outputs, or state
 
-        let proof_data = vec![0u8; 256]; // Placeholder
+        let mut proof_data = agreed_output_hash.to_vec();
+        proof_data.resize(256, 0); // Placeholder padding
 
         ExecutionProof {
             contract_address: contract_address.to_string(),
             function_hash: Self::hash_function_name(function_name),
             proof: proof_data,
             timestamp: Self::current_timestamp(),
+            signatures,
         }
     }
 
-    // Encrypt data (AES-256-GCM)
-    fn encrypt_data(&self, data: &[u8], key: &[u8]) -> Vec<u8> {
-        // Simplified encryption
-        // Production: Use proper AES-GCM with libsodium or ring
+    fn hash_bytes(data: &[u8]) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.finalize().to_vec()
+    }
 
-        let mut encrypted = data.to_vec();
-        for (i, byte) in encrypted.iter_mut(). enumerate() {
-            *byte ^= key[i % key.len()];
+    // Simplified stand-in for a real per-validator keypair signature -
+    // deterministic so independent validators that agree on the output hash
+    // also agree on the signature bytes.
+    fn sign_execution(&self, validator: &str, output_hash: &[u8]) -> ValidatorSignature {
+        let mut hasher = Sha256::new();
+        hasher.update(validator.as_bytes());
+        hasher.update(output_hash);
+        ValidatorSignature {
+            validator: validator.to_string(),
+            output_hash: output_hash.to_vec(),
+            signature: hasher.finalize().to_vec(),
         }
-
-        encrypted
     }
 
-    // Decrypt data
-    fn decrypt_data(&self, encrypted: &[u8], key: &[u8]) -> Result<Vec<u8>, String> {
-        // Simplified decryption
-        let mut decrypted = encrypted.to_vec();
-        for (i, byte) in decrypted.iter_mut().enumerate() {
-            *byte ^= key[i % key.len()];
+    // Encrypt data with AES-256-GCM under a fresh random nonce, so the same
+    // plaintext never produces the same ciphertext twice.
+    fn encrypt_data(&self, data: &[u8], key: &[u8], key_id: &str) -> EncryptedData {
+        let mut nonce_bytes = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut nonce_bytes);
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let nonce = Nonce::from_slice(&nonce_bytes);
+        let ciphertext = cipher
+            .encrypt(nonce, data)
+            .expect("AES-256-GCM encryption should never fail for in-memory buffers");
+
+        EncryptedData {
+            ciphertext,
+            encryption_key_id: key_id.to_string(),
+            nonce: nonce_bytes.to_vec(),
+            authenticated: true,
         }
+    }
 
-        Ok(decrypted)
+    // Decrypt data, verifying the GCM authentication tag - tampering or the
+    // wrong key yields an error instead of garbage plaintext.
+    fn decrypt_data(&self, encrypted: &EncryptedData, key: &[u8]) -> Result<Vec<u8>, String> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let nonce = Nonce::from_slice(&encrypted.nonce);
+
+        cipher
+            .decrypt(nonce, encrypted.ciphertext.as_ref())
+            .map_err(|_| "decryption failed: wrong key or tampered ciphertext".to_string())
     }
 
     fn hash_function_name(name: &str) -> String {
@@ -321,7 +432,8 @@ impl KeyManager {
     }
 
     fn generate_key(&mut self, contract_address: &str) -> Vec<u8> {
-        let key = vec![0xAB; 32]; // Simplified - use proper key generation
+        let mut key = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
         self.encryption_keys.insert(contract_address.to_string(), key. clone());
         key
     }
@@ -332,8 +444,133 @@ impl KeyManager {
             .ok_or("Key not found". to_string())
     }
 
-    fn verify_viewing_key(&self, _contract_address: &str, _viewing_key: &[u8]) -> bool {
-        // Simplified verification
-        true
+    /// Viewing keys are derived deterministically from the contract's own
+    /// encryption key (`SHA256(key || "viewing-key")`) instead of being
+    /// tracked as a second secret per viewer - anyone holding the contract
+    /// key can hand this out to grant selective disclosure.
+    fn derive_viewing_key(&self, contract_address: &str) -> Result<Vec<u8>, String> {
+        let key = self.get_key(contract_address)?;
+        let mut hasher = Sha256::new();
+        hasher.update(&key);
+        hasher.update(b"viewing-key");
+        Ok(hasher.finalize().to_vec())
+    }
+
+    fn verify_viewing_key(&self, contract_address: &str, viewing_key: &[u8]) -> bool {
+        match self.derive_viewing_key(contract_address) {
+            Ok(expected) => constant_time_eq(&expected, viewing_key),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deploy_and_call_round_trips_through_real_aead() {
+        let mut vm = PrivateContractVM::new();
+        let address = vm
+            .deploy_private_contract(b"bytecode".to_vec(), "owner".to_string(), vec![])
+            .unwrap();
+
+        let result = vm
+            .call_private_function(&address, "owner", "transfer", vec![])
+            .unwrap();
+
+        assert_ne!(result.ciphertext, b"result_of_transfer".to_vec());
+        assert_eq!(result.nonce.len(), 12);
+    }
+
+    #[test]
+    fn two_encryptions_of_the_same_plaintext_use_different_nonces_and_ciphertexts() {
+        let mut vm = PrivateContractVM::new();
+        let address = vm
+            .deploy_private_contract(b"bytecode".to_vec(), "owner".to_string(), vec![])
+            .unwrap();
+
+        let key = vm.key_manager.get_key(&address).unwrap();
+        let a = vm.encrypt_data(b"same plaintext", &key, &address);
+        let b = vm.encrypt_data(b"same plaintext", &key, &address);
+
+        assert_ne!(a.nonce, b.nonce);
+        assert_ne!(a.ciphertext, b.ciphertext);
+    }
+
+    #[test]
+    fn tampered_ciphertext_fails_authentication() {
+        let mut vm = PrivateContractVM::new();
+        let address = vm
+            .deploy_private_contract(b"bytecode".to_vec(), "owner".to_string(), vec![])
+            .unwrap();
+
+        let key = vm.key_manager.get_key(&address).unwrap();
+        let mut encrypted = vm.encrypt_data(b"secret", &key, &address);
+        encrypted.ciphertext[0] ^= 0xFF;
+
+        assert!(vm.decrypt_data(&encrypted, &key).is_err());
+    }
+
+    #[test]
+    fn read_private_state_requires_a_valid_viewing_key() {
+        let mut vm = PrivateContractVM::new();
+        // Writes are gated on `allowed_callers` (the owner alone is not
+        // enough), so the owner is listed there to seed some state.
+        let address = vm
+            .deploy_private_contract(b"bytecode".to_vec(), "owner".to_string(), vec!["owner".to_string()])
+            .unwrap();
+        vm.write_private_state(&address, "balance".to_string(), b"100".to_vec(), "owner")
+            .unwrap();
+
+        assert!(vm.read_private_state(&address, "balance", b"wrong key").is_err());
+
+        let viewing_key = vm.issue_viewing_key(&address, "owner").unwrap();
+        assert_eq!(vm.read_private_state(&address, "balance", &viewing_key).unwrap(), b"100");
+    }
+
+    #[test]
+    fn issue_viewing_key_is_denied_to_unauthorized_callers() {
+        let mut vm = PrivateContractVM::new();
+        let address = vm
+            .deploy_private_contract(b"bytecode".to_vec(), "owner".to_string(), vec![])
+            .unwrap();
+
+        assert!(vm.issue_viewing_key(&address, "stranger").is_err());
+    }
+
+    #[test]
+    fn call_private_function_notarizes_with_designated_validators() {
+        let mut vm = PrivateContractVM::new();
+        let address = vm
+            .deploy_private_contract(
+                b"bytecode".to_vec(),
+                "owner".to_string(),
+                vec!["validator-1".to_string(), "validator-2".to_string(), "validator-3".to_string()],
+            )
+            .unwrap();
+
+        vm.call_private_function(&address, "owner", "transfer", vec![]).unwrap();
+
+        let proof = vm.execution_proofs.last().unwrap();
+        assert_eq!(proof.signatures.len(), 3);
+        // All three validators independently re-executed the same
+        // deterministic call, so they must agree on the output hash.
+        let first_hash = &proof.signatures[0].output_hash;
+        assert!(proof.signatures.iter().all(|s| &s.output_hash == first_hash));
+    }
+
+    #[test]
+    fn select_agreed_hash_requires_the_threshold_to_actually_agree() {
+        let signatures = vec![
+            ValidatorSignature { validator: "v1".to_string(), output_hash: vec![1], signature: vec![] },
+            ValidatorSignature { validator: "v2".to_string(), output_hash: vec![2], signature: vec![] },
+            ValidatorSignature { validator: "v3".to_string(), output_hash: vec![1], signature: vec![] },
+        ];
+
+        // 2-of-3 agree on hash [1] - consensus reached.
+        assert_eq!(select_agreed_hash(&signatures, 2).unwrap(), vec![1]);
+        // Unanimous agreement is a stricter bar the above set can't clear.
+        assert!(select_agreed_hash(&signatures, 3).is_err());
     }
 }
@@ -2,6 +2,9 @@
 // App-specific rollups, fraud proofs, validity proofs
 
 use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+
+use crate::zkvm::{ZkProof, ZkVM};
 
 pub struct RollupManager {
     pub rollups: HashMap<String, Rollup>,
@@ -16,6 +19,7 @@ pub struct Rollup {
     pub name: String,
     pub operator: String,
     pub state_root: String,
+    pub da_mode: DataAvailabilityMode,
     pub transactions: Vec<RollupTransaction>,
     pub batches: Vec<RollupBatch>,
     pub active: bool,
@@ -25,9 +29,21 @@ pub struct Rollup {
 pub enum RollupType {
     Optimistic,  // Fraud proofs
     ZkRollup,    // Validity proofs
+    Validium,    // Validity proofs, data kept off-chain
     Sovereign,   // App-specific
 }
 
+/// Where a rollup's transaction data actually lives. `Optimistic` and
+/// `ZkRollup` post it `OnChain`; `Validium` keeps it with an external DA
+/// provider and only posts a commitment, trading data-availability
+/// guarantees for cheaper throughput while keeping the same validity
+/// proofs as `ZkRollup`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataAvailabilityMode {
+    OnChain,
+    OffChain { provider: String, commitment: String },
+}
+
 #[derive(Debug, Clone)]
 pub struct RollupTransaction {
     pub tx_hash: String,
@@ -44,6 +60,12 @@ pub struct RollupBatch {
     pub tx_count: usize,
     pub state_root: String,
     pub proof: Option<Vec<u8>>,  // Fraud proof or validity proof
+    /// Commitment to this batch's transaction data at its DA provider.
+    /// `None` for on-chain rollups, where the data itself is the batch.
+    pub da_commitment: Option<String>,
+    /// Whether `da_commitment` has been attested by the DA provider -
+    /// required before a Validium batch can finalize.
+    pub da_attested: bool,
     pub timestamp: u64,
     pub finalized: bool,
     pub challenge_period_end: u64,
@@ -64,6 +86,7 @@ impl RollupManager {
         name: String,
         rollup_type: RollupType,
         operator: String,
+        da_mode: DataAvailabilityMode,
     ) -> String {
         let rollup_id = format!("rollup_{}", self.total_rollups + 1);
 
@@ -73,6 +96,7 @@ impl RollupManager {
             name: name.clone(),
             operator,
             state_root: "genesis_root".to_string(),
+            da_mode,
             transactions: Vec::new(),
             batches: Vec::new(),
             active: true,
@@ -113,10 +137,15 @@ impl RollupManager {
         rollup_id: &str,
         tx_hashes: Vec<String>,
         new_state_root: String,
+        da_commitment: Option<String>,
     ) -> Result<u64, String> {
         let rollup = self.rollups.get_mut(rollup_id)
             .ok_or("Rollup not found")?;
 
+        if matches!(rollup.rollup_type, RollupType::Validium) && da_commitment.is_none() {
+            return Err("Validium batches require a DA commitment".to_string());
+        }
+
         let batch_id = rollup.batches.len() as u64 + 1;
 
         let batch = RollupBatch {
@@ -124,6 +153,8 @@ impl RollupManager {
             tx_count: tx_hashes. len(),
             state_root: new_state_root,
             proof: None,
+            da_commitment: da_commitment.clone(),
+            da_attested: false,
             timestamp: Self::current_timestamp(),
             finalized: false,
             challenge_period_end: Self::current_timestamp() + 604800, // 7 days
@@ -132,6 +163,12 @@ impl RollupManager {
         rollup.batches.push(batch);
         rollup.state_root = rollup.batches.last().unwrap().state_root.clone();
 
+        if let Some(commitment) = da_commitment {
+            if let DataAvailabilityMode::OffChain { provider, .. } = &rollup.da_mode {
+                rollup.da_mode = DataAvailabilityMode::OffChain { provider: provider.clone(), commitment };
+            }
+        }
+
         println!("📦 Batch created: {} in rollup {} ({} txs)",
             batch_id, rollup_id, tx_hashes. len());
 
@@ -188,7 +225,8 @@ impl RollupManager {
             . ok_or("Rollup not found")?;
 
         match rollup.rollup_type {
-            RollupType::ZkRollup => {
+            RollupType::ZkRollup | RollupType::Validium => {
+                let is_validium = matches!(rollup.rollup_type, RollupType::Validium);
                 let batch = rollup.batches. iter_mut()
                     . find(|b| b.batch_id == batch_id)
                     .ok_or("Batch not found")?;
@@ -196,16 +234,113 @@ impl RollupManager {
                 // Verify ZK proof
                 if self.verify_validity_proof(&validity_proof) {
                     batch.proof = Some(validity_proof);
-                    batch.finalized = true;
+                    // Validium still needs its DA commitment attested
+                    // before `finalize_batch` will accept it.
+                    batch.finalized = !is_validium;
 
-                    println!("✅ Validity proof verified: Batch {} finalized", batch_id);
+                    if is_validium {
+                        println!("✅ Validity proof verified: Batch {} awaiting DA attestation", batch_id);
+                    } else {
+                        println!("✅ Validity proof verified: Batch {} finalized", batch_id);
+                    }
 
                     Ok(())
                 } else {
                     Err("Invalid validity proof".to_string())
                 }
             }
-            _ => Err("Not a ZK rollup".to_string()),
+            _ => Err("Not a ZK rollup or Validium".to_string()),
+        }
+    }
+
+    // Submit one aggregated validity proof covering many batches at once
+    // (ZkRollup/Validium only). Verifies the proof against the claimed
+    // state root of every batch in `batch_ids`, in order, via
+    // `ZkVM::verify_aggregated`, then finalizes all of them atomically -
+    // either every batch in the list finalizes or none do.
+    pub fn submit_aggregated_validity_proof(
+        &mut self,
+        rollup_id: &str,
+        batch_ids: Vec<u64>,
+        zkvm: &ZkVM,
+        aggregated_proof: ZkProof,
+    ) -> Result<(), String> {
+        if batch_ids.is_empty() {
+            return Err("no batches to finalize".to_string());
+        }
+
+        let rollup = self.rollups.get_mut(rollup_id)
+            .ok_or("Rollup not found")?;
+
+        if !matches!(rollup.rollup_type, RollupType::ZkRollup | RollupType::Validium) {
+            return Err("Not a ZK rollup or Validium".to_string());
+        }
+        let is_validium = matches!(rollup.rollup_type, RollupType::Validium);
+
+        let mut expected_leaf_inputs = Vec::with_capacity(batch_ids.len());
+        for &batch_id in &batch_ids {
+            let batch = rollup.batches.iter()
+                .find(|b| b.batch_id == batch_id)
+                .ok_or("Batch not found")?;
+
+            if batch.finalized {
+                return Err(format!("batch {} already finalized", batch_id));
+            }
+            if is_validium && !batch.da_attested {
+                return Err(format!("batch {} awaiting DA attestation", batch_id));
+            }
+
+            expected_leaf_inputs.push(vec![Self::state_root_digest(&batch.state_root)]);
+        }
+
+        if !zkvm.verify_aggregated(&aggregated_proof, &expected_leaf_inputs) {
+            return Err("Invalid aggregated validity proof".to_string());
+        }
+
+        for batch_id in &batch_ids {
+            let batch = rollup.batches.iter_mut().find(|b| b.batch_id == *batch_id).unwrap();
+            batch.proof = Some(aggregated_proof.proof_data.clone());
+            batch.finalized = true;
+        }
+
+        println!("✅ Aggregated validity proof verified: {} batches finalized in rollup {}",
+            batch_ids.len(), rollup_id);
+
+        Ok(())
+    }
+
+    // Collapses a batch's claimed state root into the `u64` witness shape
+    // `ZkProof::public_inputs` uses, so it can stand in as a leaf input
+    // for aggregation/verification.
+    fn state_root_digest(state_root: &str) -> u64 {
+        let digest = Sha256::digest(state_root.as_bytes());
+        u64::from_le_bytes(digest[..8].try_into().unwrap())
+    }
+
+    // Submit DA attestation (Validium)
+    pub fn submit_da_attestation(
+        &mut self,
+        rollup_id: &str,
+        batch_id: u64,
+        attestation: Vec<u8>,
+    ) -> Result<(), String> {
+        let rollup = self.rollups.get_mut(rollup_id)
+            .ok_or("Rollup not found")?;
+
+        let batch = rollup.batches.iter_mut()
+            .find(|b| b.batch_id == batch_id)
+            .ok_or("Batch not found")?;
+
+        let commitment = batch.da_commitment.clone().ok_or("Batch has no DA commitment to attest")?;
+
+        if Self::verify_da_commitment(&commitment, &attestation) {
+            batch.da_attested = true;
+
+            println!("📡 DA commitment attested: batch {} in rollup {}", batch_id, rollup_id);
+
+            Ok(())
+        } else {
+            Err("Invalid DA attestation".to_string())
         }
     }
 
@@ -245,6 +380,20 @@ impl RollupManager {
 
                 println!("✅ Batch finalized: {} (validity proof)", batch_id);
             }
+            RollupType::Validium => {
+                // Same validity-proof requirement as ZkRollup, plus the
+                // off-chain data must have been attested by the DA layer.
+                if batch.proof.is_none() {
+                    return Err("No validity proof".to_string());
+                }
+                if !batch.da_attested {
+                    return Err("DA commitment has not been attested".to_string());
+                }
+
+                batch.finalized = true;
+
+                println!("✅ Batch finalized: {} (validity proof + DA attestation)", batch_id);
+            }
             RollupType::Sovereign => {
                 batch.finalized = true;
             }
@@ -303,6 +452,15 @@ impl RollupManager {
         proof.len() > 0
     }
 
+    // Verify a DA provider's attestation of a commitment (swappable per
+    // DA backend - e.g. a Celestia blob inclusion proof or an EigenDA
+    // quorum signature).
+    // Production: Verify the attestation against `commitment` for whatever
+    // DA backend is configured.
+    fn verify_da_commitment(commitment: &str, attestation: &[u8]) -> bool {
+        !commitment.is_empty() && attestation.len() > 0
+    }
+
     fn current_timestamp() -> u64 {
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -2,6 +2,7 @@
 // Execute smart contracts with ZK proofs - faster verification, compressed blocks
 
 use std::collections::HashMap;
+use sha2::{Digest, Sha256};
 
 pub struct ZkVM {
     pub state: HashMap<String, Vec<u8>>,
@@ -39,6 +40,7 @@ pub struct StateChange {
     pub new_value: Vec<u8>,
 }
 
+#[derive(Debug, Clone)]
 pub struct ZkProof {
     pub proof_data: Vec<u8>,
     pub public_inputs: Vec<u64>,
@@ -189,6 +191,84 @@ impl ZkVM {
         valid
     }
 
+    // Recursively fold many proofs into one, so a rollup can post a
+    // single aggregated proof per epoch instead of one per batch.
+    // Builds a recursion tree: pairs of proofs fold into a parent
+    // "verifier circuit" proof whose public input is the hash of its two
+    // children's public inputs, repeated level by level until one root
+    // proof remains. An odd proof out at a level carries up unfolded.
+    pub fn aggregate_proofs(&self, proofs: &[ZkProof]) -> Result<ZkProof, String> {
+        if proofs.is_empty() {
+            return Err("cannot aggregate an empty proof set".to_string());
+        }
+        if proofs.len() == 1 {
+            return Ok(proofs[0].clone());
+        }
+
+        let mut level = proofs.to_vec();
+        while level.len() > 1 {
+            let mut next = Vec::with_capacity(level.len().div_ceil(2));
+            let mut pending = level.into_iter();
+            while let Some(left) = pending.next() {
+                match pending.next() {
+                    Some(right) => next.push(Self::fold_pair(&left, &right)),
+                    None => next.push(left),
+                }
+            }
+            level = next;
+        }
+
+        Ok(level.into_iter().next().unwrap())
+    }
+
+    // Folds two sibling proofs into a parent proof. The parent's public
+    // input commits to the hash of both children's public inputs (in
+    // order), so the root of the tree transitively commits to the
+    // ordered list of every leaf's public inputs.
+    fn fold_pair(left: &ZkProof, right: &ZkProof) -> ZkProof {
+        let mut hasher = Sha256::new();
+        for input in left.public_inputs.iter().chain(right.public_inputs.iter()) {
+            hasher.update(input.to_le_bytes());
+        }
+        let digest = hasher.finalize();
+        let folded_input = u64::from_le_bytes(digest[..8].try_into().unwrap());
+
+        let mut proof_data = left.proof_data.clone();
+        proof_data.extend_from_slice(&right.proof_data);
+
+        ZkProof {
+            proof_size: proof_data.len(),
+            proof_data,
+            public_inputs: vec![folded_input],
+            generation_time_ms: left.generation_time_ms + right.generation_time_ms,
+        }
+    }
+
+    // Verifies that `root` is the aggregate of exactly the leaf proofs
+    // whose public inputs are `expected_leaf_inputs`, in that order - by
+    // recomputing the same fold tree from the expected inputs and
+    // comparing the resulting root commitment. Rejects an empty slice.
+    pub fn verify_aggregated(&self, root: &ZkProof, expected_leaf_inputs: &[Vec<u64>]) -> bool {
+        if expected_leaf_inputs.is_empty() {
+            return false;
+        }
+
+        let leaves: Vec<ZkProof> = expected_leaf_inputs
+            .iter()
+            .map(|inputs| ZkProof {
+                proof_data: Vec::new(),
+                public_inputs: inputs.clone(),
+                proof_size: 0,
+                generation_time_ms: 0,
+            })
+            .collect();
+
+        match self.aggregate_proofs(&leaves) {
+            Ok(expected_root) => expected_root.public_inputs == root.public_inputs,
+            Err(_) => false,
+        }
+    }
+
     // Compress block using ZK proofs
     pub fn compress_block(&self, transactions: Vec<Vec<u8>>) -> CompressedBlock {
         println!("🗜️ Compressing block with {} transactions.. .", transactions.len());
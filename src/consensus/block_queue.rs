@@ -0,0 +1,169 @@
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+
+use crate::core::Block;
+
+/// Snapshot of how full each stage of a [`BlockQueue`] is, for operators
+/// to watch as a congestion signal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueueInfo {
+    pub unverified_queue_size: usize,
+    pub verifying_queue_size: usize,
+    pub verified_queue_size: usize,
+}
+
+impl QueueInfo {
+    /// Blocks sitting anywhere in the queue, verified or not.
+    pub fn total_queue_size(&self) -> usize {
+        self.unverified_queue_size + self.verifying_queue_size + self.verified_queue_size
+    }
+
+    /// Blocks still waiting on or undergoing verification - the backlog
+    /// that hasn't reached the verified queue yet.
+    pub fn incomplete_queue_size(&self) -> usize {
+        self.unverified_queue_size + self.verifying_queue_size
+    }
+}
+
+/// A block that has gone through [`BlockQueue`]'s worker pool, paired
+/// with whether it (and its transactions) checked out.
+pub struct VerifiedBlock {
+    pub block: Block,
+    pub valid: bool,
+}
+
+struct Shared {
+    unverified: Mutex<VecDeque<Block>>,
+    verified: Mutex<VecDeque<VerifiedBlock>>,
+    /// Hashes anywhere in the pipeline - queued, in-flight, or sitting in
+    /// the verified queue unread - so a re-announced block is dropped
+    /// instead of verified twice. Cleared once `pop_verified` hands the
+    /// block back out.
+    processing: Mutex<HashSet<String>>,
+    verifying_count: AtomicUsize,
+    /// Wakes an idle worker when a block is pushed.
+    work: Notify,
+    /// Wakes `wait_until_empty` callers once the unverified+verifying
+    /// backlog drains to zero.
+    idle: Notify,
+}
+
+/// Worker-pool verifier sitting between block import (networking /
+/// `Mempool`) and the chain: callers push newly-received blocks with
+/// [`push_unverified`], a fixed pool of tokio tasks runs `Block::verify`
+/// plus per-transaction checks off the caller's critical path, and
+/// results land in a verified queue for the importer to drain at its own
+/// pace via [`pop_verified`].
+///
+/// [`push_unverified`]: BlockQueue::push_unverified
+/// [`pop_verified`]: BlockQueue::pop_verified
+pub struct BlockQueue {
+    shared: Arc<Shared>,
+}
+
+impl BlockQueue {
+    /// Spawns `max(num_cpus, 3) - 2` verification workers, leaving two
+    /// cores free for the networking/import threads that feed this queue.
+    pub fn new() -> Self {
+        let shared = Arc::new(Shared {
+            unverified: Mutex::new(VecDeque::new()),
+            verified: Mutex::new(VecDeque::new()),
+            processing: Mutex::new(HashSet::new()),
+            verifying_count: AtomicUsize::new(0),
+            work: Notify::new(),
+            idle: Notify::new(),
+        });
+
+        let workers = num_cpus::get().max(3) - 2;
+        for _ in 0..workers {
+            let shared = shared.clone();
+            tokio::spawn(async move {
+                Self::run_worker(shared).await;
+            });
+        }
+
+        BlockQueue { shared }
+    }
+
+    /// Queues `block` for verification. Returns `false` without queuing
+    /// it if a block with the same hash is already anywhere in the
+    /// pipeline, so a block re-announced by multiple peers is only ever
+    /// verified once.
+    pub async fn push_unverified(&self, block: Block) -> bool {
+        let mut processing = self.shared.processing.lock().await;
+        if !processing.insert(block.hash.clone()) {
+            return false;
+        }
+        drop(processing);
+
+        self.shared.unverified.lock().await.push_back(block);
+        self.shared.work.notify_one();
+        true
+    }
+
+    /// Pops the oldest verified block, if any, freeing it from the
+    /// duplicate-suppression set so a later re-announcement of the same
+    /// hash is accepted again.
+    pub async fn pop_verified(&self) -> Option<VerifiedBlock> {
+        let verified = self.shared.verified.lock().await.pop_front()?;
+        self.shared.processing.lock().await.remove(&verified.block.hash);
+        Some(verified)
+    }
+
+    pub async fn info(&self) -> QueueInfo {
+        QueueInfo {
+            unverified_queue_size: self.shared.unverified.lock().await.len(),
+            verifying_queue_size: self.shared.verifying_count.load(Ordering::SeqCst),
+            verified_queue_size: self.shared.verified.lock().await.len(),
+        }
+    }
+
+    /// Resolves once the unverified+verifying backlog is empty, for
+    /// callers that want clean shutdown or backpressure (e.g. "pause
+    /// fetching new blocks until the queue drains"). Already-drained
+    /// verified blocks that haven't been popped yet don't block this.
+    pub async fn wait_until_empty(&self) {
+        loop {
+            let info = self.info().await;
+            if info.incomplete_queue_size() == 0 {
+                return;
+            }
+            self.shared.idle.notified().await;
+        }
+    }
+
+    async fn run_worker(shared: Arc<Shared>) {
+        loop {
+            let block = shared.unverified.lock().await.pop_front();
+            let block = match block {
+                Some(block) => block,
+                None => {
+                    shared.work.notified().await;
+                    continue;
+                }
+            };
+
+            shared.verifying_count.fetch_add(1, Ordering::SeqCst);
+            let valid = block.verify() && block.transactions.iter().all(|tx| tx.verify());
+            shared.verifying_count.fetch_sub(1, Ordering::SeqCst);
+
+            shared.verified.lock().await.push_back(VerifiedBlock { block, valid });
+
+            let drained = {
+                let unverified = shared.unverified.lock().await;
+                unverified.is_empty() && shared.verifying_count.load(Ordering::SeqCst) == 0
+            };
+            if drained {
+                shared.idle.notify_waiters();
+            }
+        }
+    }
+}
+
+impl Default for BlockQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,7 @@
+pub mod block_queue;
+pub mod tx_queue;
+pub mod producer;
+
+pub use block_queue::{BlockQueue, QueueInfo, VerifiedBlock};
+pub use tx_queue::{TxQueue, TxQueueInfo};
+pub use producer::{BlockEvent, BlockProducer};
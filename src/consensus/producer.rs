@@ -1,60 +1,311 @@
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::time::{interval, Duration};
-use crate::core::{Block, Mempool, TransactionExecutor, WorldState};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tokio::time::{interval, Duration, Instant};
+use crate::core::{Block, BlockProvider, InMemoryBlockStore, Mempool, TransactionExecutor, TransactionReceipt, WorldState};
+use super::tx_queue::{TxQueue, TxQueueInfo};
+
+/// Capacity of `BlockProducer`'s event channel - subscribers slow enough
+/// to fall this many events behind start missing them (`broadcast::Receiver`
+/// reports a `Lagged` error rather than blocking the producer).
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Structured events a `BlockProducer` emits as it commits blocks, so
+/// downstream components (a wallet tracking unconfirmed vs. confirmed
+/// balances, an indexer, a websocket gateway) can subscribe instead of
+/// polling `get_current_block_number`.
+#[derive(Debug, Clone)]
+pub enum BlockEvent {
+    /// A transaction was sealed into the block just committed.
+    TxIncluded { tx_hash: String, block_number: u64 },
+    /// A transaction was dropped instead of being included (execution
+    /// failed), so it never entered a block.
+    TxDropped { tx_hash: String, reason: String },
+    /// A block was committed: `tx_hashes`/`receipts` cover only the
+    /// transactions actually sealed into it, in the same order.
+    BlockProduced {
+        number: u64,
+        hash: String,
+        tx_hashes: Vec<String>,
+        receipts: Vec<TransactionReceipt>,
+    },
+}
+
+/// How often `start` wakes to check whether enough fee-weighted routing
+/// work has piled up to bundle a block - decoupled from `block_time_ms`
+/// (now the recalibration target and the base of the liveness ceiling)
+/// so bundling can react faster than
+/// a full interval once fees accumulate.
+const POLL_INTERVAL_MS: u64 = 50;
+
+/// Hard ceiling on how long a block can go unbundled regardless of
+/// accumulated fees, expressed as a multiple of `block_time_ms`, so a
+/// quiet mempool still doesn't stall the chain.
+const LIVENESS_CEILING_MULTIPLIER: u64 = 10;
+
+const MIN_BURNFEE: f64 = 0.01;
+const MAX_BURNFEE: f64 = 1.0e12;
+
+/// How many transactions the mempool-feeder pulls per round into the
+/// `TxQueue` for pre-validation. Kept well under the mempool's `max_size`
+/// so the feeder loops frequently rather than draining it in one shot.
+const FEED_BATCH_SIZE: usize = 256;
+
+/// Capacity of the internal control channel. Bundling-related messages
+/// are cheap and idempotent to coalesce, so a small buffer is enough -
+/// a burst just means `handle_try_bundle_block` re-checks the gate a
+/// few extra times back to back.
+const CONTROL_CHANNEL_CAPACITY: usize = 64;
+
+/// Internal coordination messages driving `BlockProducer`'s bundling
+/// loop, modeled on Saito's `MempoolMessage::LocalTryBundleBlock`/
+/// `LocalNewBlock`. Never leaves the producer - unlike `BlockEvent`,
+/// which is the public, subscriber-facing stream.
+#[derive(Debug, Clone, Copy)]
+enum ControlMessage {
+    /// Re-evaluate the burn-fee gate and bundle a block if it clears.
+    /// Sent by the poll-interval ticker, and also fired immediately
+    /// whenever the mempool-feeder hands fresh transactions to
+    /// `tx_queue`, so a full mempool doesn't have to wait out a full
+    /// tick before bundling.
+    TryBundleBlock,
+    /// A block was just committed; reset the clock the burn-fee gate
+    /// measures elapsed time against.
+    NewBlockCommitted,
+}
 
 pub struct BlockProducer {
     mempool: Arc<Mempool>,
+    state: Arc<WorldState>,
     executor: Arc<TransactionExecutor>,
+    block_store: Arc<dyn BlockProvider>,
+    /// Staged verification pipeline a background feeder continuously
+    /// pulls mempool transactions into, so signature/nonce/balance
+    /// pre-checks overlap with the block interval instead of happening
+    /// inline in `produce_block`.
+    tx_queue: Arc<TxQueue>,
     current_block: Arc<RwLock<u64>>,
     last_hash: Arc<RwLock<String>>,
     validator_address: String,
     block_time_ms: u64,
+    /// Routing-work threshold a block must clear to bundle, in the same
+    /// units as `Mempool::total_pending_fees`. Decays hyperbolically with
+    /// time since the last block (see `work_needed`) and is recalibrated
+    /// toward `block_time_ms` after every block produced.
+    burnfee: Arc<RwLock<f64>>,
+    last_block_at: Arc<RwLock<Instant>>,
+    events: broadcast::Sender<BlockEvent>,
+    /// Sending half of the internal bundling-coordination channel; cloned
+    /// into the timer and mempool-feeder tasks spawned by `start`.
+    control_tx: mpsc::Sender<ControlMessage>,
+    /// Receiving half, handed off to `start`'s coordinator loop the first
+    /// (and only) time it runs. `None` after that handoff.
+    control_rx: Arc<Mutex<Option<mpsc::Receiver<ControlMessage>>>>,
 }
 
 impl BlockProducer {
+    /// `tx_workers` sizes the pre-validation pool backing `TxQueue` - see
+    /// `TxQueue::new`.
     pub fn new(
         mempool: Arc<Mempool>,
         state: Arc<WorldState>,
+        executor: Arc<TransactionExecutor>,
+        block_store: Arc<dyn BlockProvider>,
         validator_address: String,
         block_time_ms: u64,
+        tx_workers: usize,
     ) -> Self {
+        // Resume from the persisted chain tip rather than always
+        // restarting at block 0 (neptune-core initializes its
+        // block-index database and latest block the same way).
+        let current_block = block_store.latest_number();
+        let last_hash = block_store.latest_hash().unwrap_or_else(|| String::from("0x0"));
+        let (control_tx, control_rx) = mpsc::channel(CONTROL_CHANNEL_CAPACITY);
+
         BlockProducer {
             mempool,
-            executor: Arc::new(TransactionExecutor::new(state)),
-            current_block: Arc::new(RwLock::new(0)),
-            last_hash: Arc::new(RwLock::new(String::from("0x0"))),
+            executor,
+            tx_queue: Arc::new(TxQueue::new(state.clone(), tx_workers)),
+            state,
+            block_store,
+            current_block: Arc::new(RwLock::new(current_block)),
+            last_hash: Arc::new(RwLock::new(last_hash)),
             validator_address,
             block_time_ms,
+            burnfee: Arc::new(RwLock::new(block_time_ms as f64)),
+            last_block_at: Arc::new(RwLock::new(Instant::now())),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            control_tx,
+            control_rx: Arc::new(Mutex::new(Some(control_rx))),
         }
     }
 
+    /// Subscribes to this producer's `BlockEvent` stream. Each call opens
+    /// an independent receiver starting from the moment of subscription;
+    /// a receiver that falls more than `EVENT_CHANNEL_CAPACITY` events
+    /// behind observes a `Lagged` error rather than blocking the producer.
+    pub fn subscribe(&self) -> broadcast::Receiver<BlockEvent> {
+        self.events.subscribe()
+    }
+
+    /// Message-driven coordinator: a timer task and the mempool-feeder
+    /// task both push `ControlMessage`s instead of calling into bundling
+    /// logic directly, so bundling can react to a fresh arrival
+    /// immediately rather than waiting out a full `POLL_INTERVAL_MS`
+    /// tick. Each message is handled by its own small async function
+    /// (`handle_try_bundle_block`, `reset_timing`) rather than inline
+    /// here, which also means those handlers can be driven directly in
+    /// tests instead of sleeping through real intervals.
     pub async fn start(&self) {
-        let mut interval = interval(Duration::from_millis(self.block_time_ms));
-        
+        let mut control_rx = self
+            .control_rx
+            .lock()
+            .await
+            .take()
+            .expect("BlockProducer::start must only be called once");
+
+        let feed_mempool = self.mempool.clone();
+        let feed_queue = self.tx_queue.clone();
+        let feed_control = self.control_tx.clone();
+        tokio::spawn(async move {
+            Self::feed_tx_queue(feed_mempool, feed_queue, feed_control).await;
+        });
+
+        let timer_control = self.control_tx.clone();
+        tokio::spawn(async move {
+            let mut ticker = interval(Duration::from_millis(POLL_INTERVAL_MS));
+            loop {
+                ticker.tick().await;
+                if timer_control.send(ControlMessage::TryBundleBlock).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Some(message) = control_rx.recv().await {
+            match message {
+                ControlMessage::TryBundleBlock => self.handle_try_bundle_block().await,
+                ControlMessage::NewBlockCommitted => self.reset_timing().await,
+            }
+        }
+    }
+
+    /// Continuously pulls the highest-priority pending transactions out
+    /// of the mempool and hands them to `tx_queue` for pre-validation, so
+    /// that work happens during the gaps between blocks rather than
+    /// inline in `produce_block`. Fires `TryBundleBlock` after every
+    /// non-empty batch, since fresh fees can clear the burn-fee gate
+    /// before the next timer tick.
+    async fn feed_tx_queue(
+        mempool: Arc<Mempool>,
+        tx_queue: Arc<TxQueue>,
+        control_tx: mpsc::Sender<ControlMessage>,
+    ) {
         loop {
-            interval.tick().await;
-            self.produce_block().await;
+            let batch = mempool.get_transactions(FEED_BATCH_SIZE).await;
+            if batch.is_empty() {
+                tokio::time::sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+                continue;
+            }
+
+            for tx in batch {
+                tx_queue.push_unverified(tx).await;
+            }
+
+            let _ = control_tx.send(ControlMessage::TryBundleBlock).await;
         }
     }
 
-    async fn produce_block(&self) {
-        let block_number = {
-            let mut current = self.current_block. write().await;
-            *current += 1;
-            *current
-        };
+    /// Saito-style burn-fee gate: computes the routing work a block
+    /// currently needs to clear (`work_needed`, decaying hyperbolically
+    /// the longer it's been since the last block) and only bundles once
+    /// the fees collected across the mempool and the pre-validation
+    /// pipeline clear it, or once `elapsed_ms` exceeds the hard liveness
+    /// ceiling regardless of fees.
+    async fn handle_try_bundle_block(&self) {
+        let elapsed_ms = self.last_block_at.read().await.elapsed().as_millis().max(1) as u64;
+        let liveness_ceiling_ms = self.block_time_ms * LIVENESS_CEILING_MULTIPLIER;
+
+        if elapsed_ms < liveness_ceiling_ms {
+            let collected_work = (self.mempool.total_pending_fees().await
+                + self.tx_queue.total_fees().await) as f64;
+            if collected_work < self.work_needed_at(elapsed_ms).await {
+                return;
+            }
+        }
+
+        if !self.produce_block(elapsed_ms).await {
+            // Gate cleared (by fees, or by the liveness ceiling) but there
+            // was nothing to bundle - reset the clock ourselves so an idle
+            // queue past the ceiling doesn't refire on every poll tick
+            // with `elapsed_ms` never shrinking.
+            self.reset_timing().await;
+        }
+    }
+
+    /// Resets the clock the burn-fee gate measures elapsed time against:
+    /// either via `ControlMessage::NewBlockCommitted` once a block is
+    /// sealed, or directly by `handle_try_bundle_block` when the gate
+    /// cleared but there was nothing to bundle.
+    async fn reset_timing(&self) {
+        *self.last_block_at.write().await = Instant::now();
+    }
+
+    fn work_needed_for(burnfee: f64, elapsed_ms: u64) -> f64 {
+        burnfee / (elapsed_ms as f64 / 1000.0)
+    }
+
+    async fn work_needed_at(&self, elapsed_ms: u64) -> f64 {
+        Self::work_needed_for(*self.burnfee.read().await, elapsed_ms)
+    }
+
+    /// Current routing-work threshold a block must clear to bundle right
+    /// now, for operators to observe congestion.
+    pub async fn work_needed(&self) -> f64 {
+        let elapsed_ms = self.last_block_at.read().await.elapsed().as_millis().max(1) as u64;
+        self.work_needed_at(elapsed_ms).await
+    }
+
+    pub async fn burnfee(&self) -> f64 {
+        *self.burnfee.read().await
+    }
+
+    /// Depth of each stage of the pre-validation pipeline, for operators
+    /// to watch as a congestion signal alongside `work_needed`/`burnfee`.
+    pub async fn tx_queue_info(&self) -> TxQueueInfo {
+        self.tx_queue.info().await
+    }
+
+    pub fn validator_address(&self) -> &str {
+        &self.validator_address
+    }
 
+    pub fn block_time_ms(&self) -> u64 {
+        self.block_time_ms
+    }
+
+    /// Returns whether a block was actually committed. `current_block` is
+    /// only incremented once there's something to seal, so an empty queue
+    /// past the liveness ceiling can't run the counter away.
+    async fn produce_block(&self, actual_interval_ms: u64) -> bool {
         let parent_hash = self.last_hash.read().await.clone();
 
-        // Get transactions from mempool
-        let transactions = self.mempool.get_transactions(1000).await;
-        
+        // Drain already pre-validated transactions rather than pulling
+        // straight from the mempool, so the signature/nonce/balance
+        // checks `TxQueue`'s workers ran overlapped the block interval
+        // instead of happening here.
+        let transactions = self.tx_queue.take_verified(1000).await;
+
         if transactions.is_empty() {
             // No transactions, skip block
-            return;
+            return false;
         }
 
+        let block_number = {
+            let mut current = self.current_block.write().await;
+            *current += 1;
+            *current
+        };
+
         // Create new block
         let mut block = Block::new(block_number, parent_hash, self.validator_address.clone());
 
@@ -62,27 +313,189 @@ impl BlockProducer {
         let receipts = self.executor.execute_batch(transactions. clone(), block_number).await;
 
         // Add successful transactions to block
+        let mut processed_hashes = Vec::new();
+        let mut sealed_receipts = Vec::new();
         for (tx, receipt) in transactions.iter().zip(receipts.iter()) {
             if receipt.status {
+                let sealed_before = block.transactions.len();
                 block. add_transaction(tx.clone());
+                processed_hashes.push(Mempool::message_hash(tx));
+                // `add_transaction` refuses a transaction whose gas-class
+                // bucket is full; only a sealed one gets its receipt here.
+                if block.transactions.len() > sealed_before {
+                    sealed_receipts.push(receipt.clone());
+                }
+            } else {
+                let _ = self.events.send(BlockEvent::TxDropped {
+                    tx_hash: tx.hash.clone(),
+                    reason: "execution failed".to_string(),
+                });
             }
         }
 
+        // Keep the mempool's dedup cache in sync so a transaction just
+        // sealed into this block can't be re-admitted later.
+        self.mempool.register_processed(processed_hashes, block_number).await;
+
+        // Stamp the post-execution state root into the header
+        block.state_root = self.state.state_root().await;
+
         // Update last hash
         {
             let mut last_hash = self.last_hash.write().await;
             *last_hash = block.hash. clone();
         }
 
+        // Publish events after `last_hash` is updated so a subscriber
+        // that reacts to `BlockProduced` always sees a producer state
+        // consistent with the block it was just told about.
+        for tx in block.transactions.iter() {
+            let _ = self.events.send(BlockEvent::TxIncluded {
+                tx_hash: tx.hash.clone(),
+                block_number: block.number,
+            });
+        }
+        let _ = self.events.send(BlockEvent::BlockProduced {
+            number: block.number,
+            hash: block.hash.clone(),
+            tx_hashes: block.transactions.iter().map(|tx| tx.hash.clone()).collect(),
+            receipts: sealed_receipts,
+        });
+
+        // Seal the block into the persistent store so RPC queries see it
+        self.block_store.insert(block.clone());
+
+        // Recalibrate the burn fee toward the target block time: a block
+        // that arrived faster than `block_time_ms` raises the bar for the
+        // next one, a slow block lowers it.
+        {
+            let mut burnfee = self.burnfee.write().await;
+            let ratio = self.block_time_ms as f64 / actual_interval_ms as f64;
+            *burnfee = (*burnfee * ratio.sqrt()).clamp(MIN_BURNFEE, MAX_BURNFEE);
+        }
+
+        // Let the coordinator reset the bundling clock rather than doing
+        // it inline here, so timing state changes in one place.
+        let _ = self.control_tx.send(ControlMessage::NewBlockCommitted).await;
+
         println!(
             "⛓️  Block #{} produced | {} txs | Hash: {}",
             block. number,
             block.transactions. len(),
             &block.hash[..16]
         );
+
+        true
     }
 
     pub async fn get_current_block_number(&self) -> u64 {
         *self.current_block.read(). await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Mempool, Transaction, TransactionExecutor, WorldState};
+
+    async fn sample_producer() -> (BlockProducer, Arc<Mempool>, Arc<WorldState>) {
+        let state = Arc::new(WorldState::new());
+        // Enough to cover the default 1 gwei x 21,000 gas fee that
+        // `TxQueue`'s balance pre-check adds on top of the value.
+        state.create_account("alice".to_string(), 1_000_000_000_000_000).await;
+        let mempool = Arc::new(Mempool::new(100));
+        let block_store = Arc::new(InMemoryBlockStore::new());
+        let executor = Arc::new(TransactionExecutor::new(state.clone()));
+
+        let producer = BlockProducer::new(
+            mempool.clone(),
+            state.clone(),
+            executor,
+            block_store,
+            "validator".to_string(),
+            1000,
+            2,
+        );
+
+        (producer, mempool, state)
+    }
+
+    #[tokio::test]
+    async fn subscriber_sees_exactly_the_committed_set() {
+        let (producer, _mempool, _state) = sample_producer().await;
+        let mut events = producer.subscribe();
+
+        let tx = Transaction::new("alice".to_string(), "bob".to_string(), 10, 0);
+        let tx_hash = tx.hash.clone();
+        producer.tx_queue.push_unverified(tx).await;
+        producer.tx_queue.wait_until_empty().await;
+
+        producer.produce_block(1000).await;
+
+        let mut included = Vec::new();
+        let mut produced = None;
+        while let Ok(event) = events.try_recv() {
+            match event {
+                BlockEvent::TxIncluded { tx_hash, .. } => included.push(tx_hash),
+                BlockEvent::BlockProduced { tx_hashes, .. } => produced = Some(tx_hashes),
+                BlockEvent::TxDropped { .. } => panic!("transaction unexpectedly dropped"),
+            }
+        }
+
+        assert_eq!(included, vec![tx_hash.clone()]);
+        assert_eq!(produced, Some(vec![tx_hash]));
+    }
+
+    #[tokio::test]
+    async fn try_bundle_block_commits_and_fires_new_block_committed() {
+        let (producer, _mempool, _state) = sample_producer().await;
+
+        let tx = Transaction::new("alice".to_string(), "bob".to_string(), 10, 0);
+        producer.tx_queue.push_unverified(tx).await;
+        producer.tx_queue.wait_until_empty().await;
+
+        // Drive the coordinator's message handlers directly instead of
+        // spawning `start` and waiting out real poll intervals: force the
+        // gate open, then hand-deliver the control message it would have
+        // received from the timer task.
+        *producer.burnfee.write().await = 0.0;
+        producer.handle_try_bundle_block().await;
+
+        assert_eq!(producer.get_current_block_number().await, 1);
+        // `produce_block` should have enqueued a `NewBlockCommitted`
+        // rather than resetting the timer itself.
+        let message = producer
+            .control_rx
+            .lock()
+            .await
+            .as_mut()
+            .expect("control_rx not yet taken by start()")
+            .try_recv()
+            .expect("produce_block should have sent NewBlockCommitted");
+        assert!(matches!(message, ControlMessage::NewBlockCommitted));
+    }
+
+    #[tokio::test]
+    async fn idle_queue_past_the_liveness_ceiling_does_not_run_the_block_counter_away() {
+        let (producer, _mempool, _state) = sample_producer().await;
+
+        // Past the liveness ceiling, with nothing queued to bundle.
+        *producer.last_block_at.write().await =
+            Instant::now() - Duration::from_millis(producer.block_time_ms() * LIVENESS_CEILING_MULTIPLIER + 1);
+
+        producer.handle_try_bundle_block().await;
+        producer.handle_try_bundle_block().await;
+        producer.handle_try_bundle_block().await;
+
+        // No transactions were ever available to seal, so no block should
+        // have been committed - and critically, `current_block` must not
+        // have been incremented on the empty early-return path.
+        assert_eq!(producer.get_current_block_number().await, 0);
+
+        // The first call should have reset the clock itself (since nothing
+        // was bundled), rather than leaving `elapsed_ms` pinned above the
+        // ceiling so every subsequent poll tick fires again immediately;
+        // the later two calls stop at the fee gate.
+        assert!(producer.last_block_at.read().await.elapsed() < Duration::from_millis(producer.block_time_ms() * LIVENESS_CEILING_MULTIPLIER));
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+
+use crate::core::{Transaction, WorldState};
+
+/// Snapshot of how full each stage of a [`TxQueue`] is, for operators to
+/// watch as a congestion signal (mirrors [`crate::consensus::QueueInfo`],
+/// `TxQueue`'s block-level counterpart).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TxQueueInfo {
+    pub unverified_queue_size: usize,
+    pub verifying_queue_size: usize,
+    pub verified_queue_size: usize,
+}
+
+impl TxQueueInfo {
+    /// Transactions sitting anywhere in the queue, verified or not.
+    pub fn total_queue_size(&self) -> usize {
+        self.unverified_queue_size + self.verifying_queue_size + self.verified_queue_size
+    }
+
+    /// Transactions still waiting on or undergoing verification - the
+    /// backlog that hasn't reached the verified queue yet.
+    pub fn incomplete_queue_size(&self) -> usize {
+        self.unverified_queue_size + self.verifying_queue_size
+    }
+}
+
+struct Shared {
+    unverified: Mutex<VecDeque<(u64, Transaction)>>,
+    /// Finished pre-checks land here keyed by their push-order `seq`,
+    /// possibly out of order (workers race each other), until the
+    /// contiguous run starting at `next_to_emit` is promoted into
+    /// `verified` - so senders never see their own transactions reordered
+    /// by which worker happened to finish first.
+    completed: Mutex<BTreeMap<u64, Option<Transaction>>>,
+    next_to_emit: AtomicU64,
+    verified: Mutex<VecDeque<Transaction>>,
+    /// Hashes that failed pre-checks, kept indefinitely (not just while
+    /// in flight) so a resubmitted bad transaction is rejected on sight
+    /// instead of being re-verified.
+    bad: Mutex<HashSet<String>>,
+    /// Hashes anywhere in the unverified/verifying/verified pipeline
+    /// right now, so the same hash pushed twice while in flight is only
+    /// verified once.
+    processing: Mutex<HashSet<String>>,
+    verifying_count: AtomicUsize,
+    next_seq: AtomicU64,
+    state: Arc<WorldState>,
+    /// Wakes an idle worker when a transaction is pushed.
+    work: Notify,
+    /// Wakes `wait_until_empty` callers once the unverified+verifying
+    /// backlog drains to zero.
+    idle: Notify,
+}
+
+/// Multi-stage transaction verification pipeline sitting between the
+/// mempool and block assembly, modeled on [`crate::consensus::BlockQueue`]
+/// but for transactions: a configurable pool of workers pulls from an
+/// `unverified` queue, runs signature/nonce/balance pre-checks against the
+/// shared, live `WorldState` in parallel, and deposits the result into a
+/// `verified` queue - preserving each transaction's push order so
+/// `produce_block` can drain already-prevalidated transactions without
+/// expensive per-tx checks blocking the block interval.
+///
+/// These are pre-checks only: `TransactionExecutor` still performs the
+/// authoritative balance/state mutation at execution time, so a
+/// transaction that passed here can still fail execution if state moved
+/// on (e.g. a competing transfer from the same sender landed first).
+pub struct TxQueue {
+    shared: Arc<Shared>,
+}
+
+impl TxQueue {
+    /// Spawns `workers` verification tasks pulling against `state`.
+    pub fn new(state: Arc<WorldState>, workers: usize) -> Self {
+        let shared = Arc::new(Shared {
+            unverified: Mutex::new(VecDeque::new()),
+            completed: Mutex::new(BTreeMap::new()),
+            next_to_emit: AtomicU64::new(0),
+            verified: Mutex::new(VecDeque::new()),
+            bad: Mutex::new(HashSet::new()),
+            processing: Mutex::new(HashSet::new()),
+            verifying_count: AtomicUsize::new(0),
+            next_seq: AtomicU64::new(0),
+            state,
+            work: Notify::new(),
+            idle: Notify::new(),
+        });
+
+        for _ in 0..workers.max(1) {
+            let shared = shared.clone();
+            tokio::spawn(async move {
+                Self::run_worker(shared).await;
+            });
+        }
+
+        TxQueue { shared }
+    }
+
+    /// Queues `tx` for pre-check verification. Returns `false` without
+    /// queuing it if the hash already failed a previous pre-check or is
+    /// already anywhere in the pipeline.
+    pub async fn push_unverified(&self, tx: Transaction) -> bool {
+        if self.shared.bad.lock().await.contains(&tx.hash) {
+            return false;
+        }
+
+        let mut processing = self.shared.processing.lock().await;
+        if !processing.insert(tx.hash.clone()) {
+            return false;
+        }
+        drop(processing);
+
+        let seq = self.shared.next_seq.fetch_add(1, Ordering::SeqCst);
+        self.shared.unverified.lock().await.push_back((seq, tx));
+        self.shared.work.notify_one();
+        true
+    }
+
+    /// Drains up to `max` already-verified transactions, oldest-pushed
+    /// first, freeing their hashes from the duplicate-suppression set.
+    pub async fn take_verified(&self, max: usize) -> Vec<Transaction> {
+        let mut verified = self.shared.verified.lock().await;
+        let mut processing = self.shared.processing.lock().await;
+        let mut result = Vec::new();
+
+        for _ in 0..max {
+            let Some(tx) = verified.pop_front() else { break };
+            processing.remove(&tx.hash);
+            result.push(tx);
+        }
+
+        result
+    }
+
+    /// Sum of `gas_price * gas_limit` across the transactions in the
+    /// unverified queue and the not-yet-drained verified queue, so callers
+    /// gating on total routing work don't lose sight of fees that left the
+    /// mempool but haven't reached a block yet. A transaction a worker is
+    /// checking right now, or one that passed but waits on an earlier push
+    /// before promotion, is not counted.
+    pub async fn total_fees(&self) -> u64 {
+        let unverified_fees: u64 = self.shared.unverified.lock().await
+            .iter()
+            .map(|(_, tx)| tx.gas_price.saturating_mul(tx.gas_limit))
+            .sum();
+        let verified_fees: u64 = self.shared.verified.lock().await
+            .iter()
+            .map(|tx| tx.gas_price.saturating_mul(tx.gas_limit))
+            .sum();
+        unverified_fees + verified_fees
+    }
+
+    pub async fn info(&self) -> TxQueueInfo {
+        TxQueueInfo {
+            unverified_queue_size: self.shared.unverified.lock().await.len(),
+            verifying_queue_size: self.shared.verifying_count.load(Ordering::SeqCst),
+            verified_queue_size: self.shared.verified.lock().await.len(),
+        }
+    }
+
+    /// Resolves once the unverified+verifying backlog is empty.
+    pub async fn wait_until_empty(&self) {
+        loop {
+            let info = self.info().await;
+            if info.incomplete_queue_size() == 0 {
+                return;
+            }
+            self.shared.idle.notified().await;
+        }
+    }
+
+    /// Signature, nonce and balance pre-checks plus fee computation - the
+    /// expensive-but-parallelizable work this queue exists to overlap
+    /// with the block interval.
+    async fn precheck(state: &WorldState, tx: &Transaction) -> bool {
+        if !tx.verify() {
+            return false;
+        }
+
+        if tx.nonce < state.get_nonce(&tx.from).await {
+            return false;
+        }
+
+        let fee = tx.gas_price.saturating_mul(tx.gas_limit);
+        let required = fee.saturating_add(tx.value);
+        state.get_balance(&tx.from).await.unwrap_or(0) >= required
+    }
+
+    async fn run_worker(shared: Arc<Shared>) {
+        loop {
+            let next = shared.unverified.lock().await.pop_front();
+            let (seq, tx) = match next {
+                Some(item) => item,
+                None => {
+                    shared.work.notified().await;
+                    continue;
+                }
+            };
+
+            shared.verifying_count.fetch_add(1, Ordering::SeqCst);
+            let valid = Self::precheck(&shared.state, &tx).await;
+            shared.verifying_count.fetch_sub(1, Ordering::SeqCst);
+
+            if valid {
+                let mut completed = shared.completed.lock().await;
+                completed.insert(seq, Some(tx));
+            } else {
+                shared.bad.lock().await.insert(tx.hash.clone());
+                shared.processing.lock().await.remove(&tx.hash);
+                let mut completed = shared.completed.lock().await;
+                completed.insert(seq, None);
+            }
+
+            // Promote the contiguous run of completed slots starting at
+            // `next_to_emit` into the verified queue, in push order.
+            let mut completed = shared.completed.lock().await;
+            let mut verified = shared.verified.lock().await;
+            loop {
+                let next_to_emit = shared.next_to_emit.load(Ordering::SeqCst);
+                match completed.remove(&next_to_emit) {
+                    Some(Some(tx)) => {
+                        verified.push_back(tx);
+                        shared.next_to_emit.fetch_add(1, Ordering::SeqCst);
+                    }
+                    Some(None) => {
+                        shared.next_to_emit.fetch_add(1, Ordering::SeqCst);
+                    }
+                    None => break,
+                }
+            }
+            drop(verified);
+            drop(completed);
+
+            shared.idle.notify_waiters();
+        }
+    }
+}
@@ -3,6 +3,83 @@ use sha2::{Sha256, Digest};
 use chrono::Utc;
 use super::transaction::Transaction;
 
+/// Dispatch class a transaction is charged against, mirroring Substrate's
+/// `DispatchClass`: `Normal` is ordinary user traffic, `Operational` is
+/// system/privileged traffic that should keep headroom even when `Normal`
+/// is congested, and `Mandatory` (e.g. inherents) must always be included
+/// regardless of how full its bucket is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GasClass {
+    Normal,
+    Operational,
+    Mandatory,
+}
+
+/// Per-class gas budget for a block: `limit` is the bucket's total
+/// capacity, `base_extrinsic` is the fixed cost charged just for
+/// including a transaction of this class, on top of its own `gas_limit`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ClassWeight {
+    pub limit: u64,
+    pub base_extrinsic: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BlockWeights {
+    pub normal: ClassWeight,
+    pub operational: ClassWeight,
+    pub mandatory: ClassWeight,
+}
+
+impl BlockWeights {
+    pub fn class(&self, class: GasClass) -> ClassWeight {
+        match class {
+            GasClass::Normal => self.normal,
+            GasClass::Operational => self.operational,
+            GasClass::Mandatory => self.mandatory,
+        }
+    }
+}
+
+impl Default for BlockWeights {
+    fn default() -> Self {
+        // Splits the old flat 30M gas_limit so Normal keeps the bulk of
+        // the block while Operational/Mandatory get dedicated headroom
+        // that ordinary congestion can't eat into.
+        BlockWeights {
+            normal: ClassWeight { limit: 25_000_000, base_extrinsic: 21_000 },
+            operational: ClassWeight { limit: 4_000_000, base_extrinsic: 21_000 },
+            mandatory: ClassWeight { limit: 1_000_000, base_extrinsic: 0 },
+        }
+    }
+}
+
+/// Gas consumed so far in each class's bucket.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ClassConsumed {
+    pub normal: u64,
+    pub operational: u64,
+    pub mandatory: u64,
+}
+
+impl ClassConsumed {
+    pub fn get(&self, class: GasClass) -> u64 {
+        match class {
+            GasClass::Normal => self.normal,
+            GasClass::Operational => self.operational,
+            GasClass::Mandatory => self.mandatory,
+        }
+    }
+
+    fn set(&mut self, class: GasClass, value: u64) {
+        match class {
+            GasClass::Normal => self.normal = value,
+            GasClass::Operational => self.operational = value,
+            GasClass::Mandatory => self.mandatory = value,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub number: u64,
@@ -11,8 +88,8 @@ pub struct Block {
     pub parent_hash: String,
     pub hash: String,
     pub state_root: String,
-    pub gas_used: u64,
-    pub gas_limit: u64,
+    pub weights: BlockWeights,
+    pub consumed: ClassConsumed,
     pub validator: String,
 }
 
@@ -25,23 +102,35 @@ impl Block {
             parent_hash: parent_hash.clone(),
             hash: String::new(),
             state_root: String::from("0x0"),
-            gas_used: 0,
-            gas_limit: 30_000_000,
+            weights: BlockWeights::default(),
+            consumed: ClassConsumed::default(),
             validator,
         };
         block.hash = block.calculate_hash();
         block
     }
 
+    /// Gas still available in `class`'s bucket. `Mandatory` transactions
+    /// bypass this - they're always admitted - so this is only a hint for
+    /// `Normal`/`Operational` callers deciding what else still fits.
+    pub fn headroom(&self, class: GasClass) -> u64 {
+        self.weights.class(class).limit.saturating_sub(self.consumed.get(class))
+    }
+
     pub fn add_transaction(&mut self, tx: Transaction) -> bool {
-        if self.gas_used + tx.gas_limit <= self.gas_limit {
-            self.gas_used += tx.gas_limit;
-            self.transactions.push(tx);
-            self.hash = self.calculate_hash();
-            true
-        } else {
-            false
+        let class = tx.gas_class;
+        let weight = self.weights.class(class);
+        let cost = weight.base_extrinsic + tx.gas_limit;
+        let used = self.consumed.get(class);
+
+        if class != GasClass::Mandatory && used + cost > weight.limit {
+            return false;
         }
+
+        self.consumed.set(class, used + cost);
+        self.transactions.push(tx);
+        self.hash = self.calculate_hash();
+        true
     }
 
     pub fn calculate_hash(&self) -> String {
@@ -62,12 +151,13 @@ impl Block {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockHeader {
     pub number: u64,
     pub hash: String,
     pub parent_hash: String,
     pub timestamp: i64,
+    pub state_root: String,
 }
 
 impl From<&Block> for BlockHeader {
@@ -77,6 +167,7 @@ impl From<&Block> for BlockHeader {
             hash: block. hash.clone(),
             parent_hash: block.parent_hash. clone(),
             timestamp: block. timestamp,
+            state_root: block.state_root.clone(),
         }
     }
 }
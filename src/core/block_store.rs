@@ -0,0 +1,281 @@
+// Persistent block storage, queried through a BlockProvider trait so RPC
+// handlers stop fabricating block data. `InMemoryBlockStore` is the
+// dev/test backend; `SqliteBlockStore` is the default for a real node,
+// since a validator's chain state needs to survive a restart.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use super::block::{Block, BlockHeader};
+
+/// Everything about a block besides its header: parent linkage, height,
+/// and how many transactions it carries.
+#[derive(Debug, Clone)]
+pub struct BlockDetails {
+    pub parent_hash: String,
+    pub number: u64,
+    pub transaction_count: usize,
+}
+
+/// Query interface over stored blocks, keyed by either height or hash.
+/// Implementations may be in-memory or backed by an on-disk index;
+/// callers should depend on the trait, not a concrete store.
+pub trait BlockProvider: Send + Sync {
+    fn is_known(&self, hash: &str) -> bool;
+    fn block(&self, hash: &str) -> Option<Block>;
+    fn block_hash(&self, number: u64) -> Option<String>;
+    fn block_header(&self, hash: &str) -> Option<BlockHeader>;
+    fn block_details(&self, hash: &str) -> Option<BlockDetails>;
+    /// Persists `block`, indexing it by both height and hash and
+    /// advancing the chain tip.
+    fn insert(&self, block: Block);
+    /// Height of the chain tip, or 0 if the store is empty (genesis is
+    /// block 1 elsewhere in this codebase).
+    fn latest_number(&self) -> u64;
+    fn latest_hash(&self) -> Option<String>;
+}
+
+/// Convenience aliases over the trait's hash/number lookups, named to
+/// match how callers ask for a block rather than how it's keyed
+/// internally.
+impl dyn BlockProvider {
+    pub fn get_block_by_hash(&self, hash: &str) -> Option<Block> {
+        self.block(hash)
+    }
+
+    pub fn get_block_by_number(&self, number: u64) -> Option<Block> {
+        self.block(&self.block_hash(number)?)
+    }
+}
+
+/// In-memory block store keyed by both height and hash. Fast and simple,
+/// but its contents don't survive a restart - use `SqliteBlockStore` for
+/// a real node.
+pub struct InMemoryBlockStore {
+    by_hash: RwLock<HashMap<String, Block>>,
+    by_number: RwLock<HashMap<u64, String>>,
+}
+
+impl InMemoryBlockStore {
+    pub fn new() -> Self {
+        Self {
+            by_hash: RwLock::new(HashMap::new()),
+            by_number: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl BlockProvider for InMemoryBlockStore {
+    fn is_known(&self, hash: &str) -> bool {
+        self.by_hash.read().unwrap().contains_key(hash)
+    }
+
+    fn block(&self, hash: &str) -> Option<Block> {
+        self.by_hash.read().unwrap().get(hash).cloned()
+    }
+
+    fn block_hash(&self, number: u64) -> Option<String> {
+        self.by_number.read().unwrap().get(&number).cloned()
+    }
+
+    fn block_header(&self, hash: &str) -> Option<BlockHeader> {
+        self.block(hash).as_ref().map(BlockHeader::from)
+    }
+
+    fn block_details(&self, hash: &str) -> Option<BlockDetails> {
+        let block = self.block(hash)?;
+        Some(BlockDetails {
+            parent_hash: block.parent_hash,
+            number: block.number,
+            transaction_count: block.transactions.len(),
+        })
+    }
+
+    fn insert(&self, block: Block) {
+        self.by_number.write().unwrap().insert(block.number, block.hash.clone());
+        self.by_hash.write().unwrap().insert(block.hash.clone(), block);
+    }
+
+    fn latest_number(&self) -> u64 {
+        self.by_number.read().unwrap().keys().copied().max().unwrap_or(0)
+    }
+
+    fn latest_hash(&self) -> Option<String> {
+        let by_number = self.by_number.read().unwrap();
+        let latest = *by_number.keys().max()?;
+        by_number.get(&latest).cloned()
+    }
+}
+
+/// On-disk block index backed by sqlite (mirroring `SqlMarketplaceStorage`'s
+/// native backend in `l2_vm::nft_marketplace`), so a validator's chain
+/// state survives a restart instead of always resuming from block 0 -
+/// the same startup pattern neptune-core uses for its own block-index
+/// database and latest-block record. Blocks are serialized with
+/// `serde_json` and stored keyed by hash, with a `by_number` index and a
+/// single `tip` row tracking the latest (number, hash) pair so
+/// `BlockProducer::new` can resume from where it left off.
+pub struct SqliteBlockStore {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+}
+
+impl SqliteBlockStore {
+    /// Opens (creating if necessary) the block index at `path`. Separate
+    /// validators should point at separate paths so their data directories
+    /// stay isolated.
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| format!("failed to open block store db: {}", e))?;
+        Self::init_schema(&conn)?;
+        Ok(Self { conn: std::sync::Mutex::new(conn) })
+    }
+
+    pub fn in_memory() -> Result<Self, String> {
+        let conn = rusqlite::Connection::open_in_memory()
+            .map_err(|e| format!("failed to open in-memory block store db: {}", e))?;
+        Self::init_schema(&conn)?;
+        Ok(Self { conn: std::sync::Mutex::new(conn) })
+    }
+
+    fn init_schema(conn: &rusqlite::Connection) -> Result<(), String> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS blocks (
+                hash TEXT PRIMARY KEY,
+                number INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS blocks_by_number ON blocks(number);
+            CREATE TABLE IF NOT EXISTS tip (
+                id INTEGER PRIMARY KEY CHECK (id = 0),
+                number INTEGER NOT NULL,
+                hash TEXT NOT NULL
+            );",
+        )
+        .map_err(|e| format!("failed to init block store schema: {}", e))
+    }
+
+    fn row_to_block(row: &rusqlite::Row) -> rusqlite::Result<Block> {
+        let data: String = row.get(0)?;
+        serde_json::from_str(&data)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))
+    }
+}
+
+impl BlockProvider for SqliteBlockStore {
+    fn is_known(&self, hash: &str) -> bool {
+        self.block(hash).is_some()
+    }
+
+    fn block(&self, hash: &str) -> Option<Block> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT data FROM blocks WHERE hash = ?1", [hash], Self::row_to_block).ok()
+    }
+
+    fn block_hash(&self, number: u64) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT hash FROM blocks WHERE number = ?1", [number as i64], |row| row.get(0)).ok()
+    }
+
+    fn block_header(&self, hash: &str) -> Option<BlockHeader> {
+        self.block(hash).as_ref().map(BlockHeader::from)
+    }
+
+    fn block_details(&self, hash: &str) -> Option<BlockDetails> {
+        let block = self.block(hash)?;
+        Some(BlockDetails {
+            parent_hash: block.parent_hash,
+            number: block.number,
+            transaction_count: block.transactions.len(),
+        })
+    }
+
+    fn insert(&self, block: Block) {
+        let data = serde_json::to_string(&block).expect("Block always serializes");
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO blocks (hash, number, data) VALUES (?1, ?2, ?3)",
+            rusqlite::params![block.hash, block.number as i64, data],
+        )
+        .expect("block store insert");
+        conn.execute(
+            "INSERT INTO tip (id, number, hash) VALUES (0, ?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET number = excluded.number, hash = excluded.hash",
+            rusqlite::params![block.number as i64, block.hash],
+        )
+        .expect("block store tip update");
+    }
+
+    fn latest_number(&self) -> u64 {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT number FROM tip WHERE id = 0", [], |row| row.get::<_, i64>(0))
+            .map(|n| n as u64)
+            .unwrap_or(0)
+    }
+
+    fn latest_hash(&self) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT hash FROM tip WHERE id = 0", [], |row| row.get(0)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_block(number: u64) -> Block {
+        Block::new(number, "0xparent".to_string(), "0xvalidator".to_string())
+    }
+
+    #[test]
+    fn lookup_by_number_and_hash_agree() {
+        let store = InMemoryBlockStore::new();
+        let block = sample_block(1);
+        let hash = block.hash.clone();
+        store.insert(block);
+
+        assert!(store.is_known(&hash));
+        assert_eq!(store.block_hash(1), Some(hash.clone()));
+        assert_eq!(store.block(&hash).unwrap().number, 1);
+        assert_eq!(store.latest_number(), 1);
+    }
+
+    #[test]
+    fn unknown_hash_returns_none() {
+        let store = InMemoryBlockStore::new();
+        assert!(!store.is_known("0xmissing"));
+        assert!(store.block_details("0xmissing").is_none());
+    }
+
+    #[test]
+    fn sqlite_store_survives_a_reopen() {
+        let dir = std::env::temp_dir().join(format!("nusa-block-store-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("blocks.sqlite3");
+        let path = path.to_str().unwrap();
+
+        let hash = {
+            let store = SqliteBlockStore::open(path).unwrap();
+            let block = sample_block(1);
+            let hash = block.hash.clone();
+            store.insert(block);
+            assert_eq!(store.latest_number(), 1);
+            assert_eq!(store.latest_hash(), Some(hash.clone()));
+            hash
+        };
+
+        // Reopening the same path should pick the tip back up from disk.
+        let reopened = SqliteBlockStore::open(path).unwrap();
+        assert!(reopened.is_known(&hash));
+        assert_eq!(reopened.latest_number(), 1);
+        assert_eq!(reopened.latest_hash(), Some(hash));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn sqlite_store_unknown_hash_returns_none() {
+        let store = SqliteBlockStore::in_memory().unwrap();
+        assert!(!store.is_known("0xmissing"));
+        assert!(store.block_details("0xmissing").is_none());
+    }
+}
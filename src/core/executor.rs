@@ -1,30 +1,91 @@
 use super::transaction::{Transaction, TransactionReceipt};
 use super::state::WorldState;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::RwLock;
+use l2_vm::{ContractAccount, NusaVM};
 
 pub struct TransactionExecutor {
     state: Arc<WorldState>,
+    receipts: Arc<RwLock<HashMap<String, TransactionReceipt>>>,
+    vm: NusaVM,
 }
 
 impl TransactionExecutor {
     pub fn new(state: Arc<WorldState>) -> Self {
-        TransactionExecutor { state }
+        TransactionExecutor {
+            state,
+            receipts: Arc::new(RwLock::new(HashMap::new())),
+            vm: NusaVM::new(),
+        }
+    }
+
+    /// A contract call's `data` is `method\0args` - the method name as
+    /// UTF-8, a nul separator, then the raw argument bytes. Data with no
+    /// separator is treated as a zero-arg call to the whole string.
+    fn decode_call_data(data: &[u8]) -> (String, Vec<u8>) {
+        match data.iter().position(|&b| b == 0) {
+            Some(idx) => (String::from_utf8_lossy(&data[..idx]).to_string(), data[idx + 1..].to_vec()),
+            None => (String::from_utf8_lossy(data).to_string(), Vec::new()),
+        }
+    }
+
+    /// Routes calls into accounts carrying wasm bytecode through `NusaVM`
+    /// instead of the plain-transfer path. Returns `None` when `tx.to`
+    /// isn't a contract account, so the caller falls through to `transfer`.
+    async fn execute_contract_call(&self, tx: &Transaction, block_number: u64) -> Option<TransactionReceipt> {
+        let target = self.state.get_account(&tx.to).await?;
+        if target.code.is_empty() {
+            return None;
+        }
+
+        let (method, args) = Self::decode_call_data(&tx.data);
+        let account = ContractAccount { balance: target.balance, storage: target.storage };
+
+        let receipt = match self.vm.execute(&target.code, account, &method, &args, tx.gas_limit) {
+            Ok(outcome) => {
+                self.state.set_storage(&tx.to, outcome.account.storage).await;
+                TransactionReceipt {
+                    tx_hash: tx.hash.clone(),
+                    block_number,
+                    gas_used: outcome.gas_used,
+                    status: true,
+                    logs: outcome.logs,
+                }
+            }
+            Err(e) => TransactionReceipt {
+                tx_hash: tx.hash.clone(),
+                block_number,
+                gas_used: tx.gas_limit,
+                status: false,
+                logs: vec![format!("contract execution failed: {}", e)],
+            },
+        };
+
+        self.receipts.write().await.insert(receipt.tx_hash.clone(), receipt.clone());
+        Some(receipt)
     }
 
     pub async fn execute(&self, tx: &Transaction, block_number: u64) -> TransactionReceipt {
         // Verify transaction
         if !tx.verify() {
-            return TransactionReceipt {
+            let receipt = TransactionReceipt {
                 tx_hash: tx.hash.clone(),
                 block_number,
                 gas_used: 0,
                 status: false,
                 logs: vec! ["Transaction verification failed".to_string()],
             };
+            self.receipts.write().await.insert(receipt.tx_hash.clone(), receipt.clone());
+            return receipt;
+        }
+
+        if let Some(receipt) = self.execute_contract_call(tx, block_number).await {
+            return receipt;
         }
 
         // Execute transfer
-        match self.state.transfer(&tx. from, &tx.to, tx.value).await {
+        let receipt = match self.state.transfer(&tx. from, &tx.to, tx.value).await {
             Ok(_) => TransactionReceipt {
                 tx_hash: tx.hash.clone(),
                 block_number,
@@ -39,7 +100,16 @@ impl TransactionExecutor {
                 status: false,
                 logs: vec![format!("Execution failed: {}", e)],
             },
-        }
+        };
+
+        self.receipts.write().await.insert(receipt.tx_hash.clone(), receipt.clone());
+        receipt
+    }
+
+    /// Look up the receipt for a previously-executed transaction, for
+    /// clients polling `eth_getTransactionReceipt`.
+    pub async fn get_receipt(&self, tx_hash: &str) -> Option<TransactionReceipt> {
+        self.receipts.read().await.get(tx_hash).cloned()
     }
 
     pub async fn execute_batch(&self, transactions: Vec<Transaction>, block_number: u64) -> Vec<TransactionReceipt> {
@@ -53,37 +123,96 @@ impl TransactionExecutor {
         receipts
     }
 
-    // Parallel execution using rayon
-    pub async fn execute_parallel(&self, transactions: Vec<Transaction>, block_number: u64) -> Vec<TransactionReceipt> {
-        use rayon::prelude::*;
-        
-        let executor = Arc::new(self.state.clone());
-        
-        // Group transactions by sender to avoid conflicts
-        let mut groups: Vec<Vec<Transaction>> = Vec::new();
-        let mut current_group = Vec::new();
-        let mut seen_senders = std::collections::HashSet::new();
+    /// Splits `transactions` into batches where no two transactions touch
+    /// the same account (by `from` or `to`), preserving each sender's
+    /// relative order across batches - a transaction never lands in an
+    /// earlier batch than a prior transaction from the same sender. Greedy:
+    /// each tx goes into the earliest batch at or after its sender's floor
+    /// that doesn't conflict with what's already in it.
+    fn partition_into_conflict_free_batches(transactions: Vec<Transaction>) -> Vec<Vec<Transaction>> {
+        struct Batch {
+            txs: Vec<Transaction>,
+            accounts: std::collections::HashSet<String>,
+        }
+
+        let mut batches: Vec<Batch> = Vec::new();
+        let mut floor_for_sender: HashMap<String, usize> = HashMap::new();
 
         for tx in transactions {
-            if seen_senders.contains(&tx.from) {
-                groups.push(current_group);
-                current_group = Vec::new();
-                seen_senders.clear();
-            }
-            seen_senders.insert(tx. from.clone());
-            current_group.push(tx);
-        }
-        if !current_group.is_empty() {
-            groups.push(current_group);
+            let read_write_set: std::collections::HashSet<String> =
+                [tx.from.clone(), tx.to.clone()].into_iter().collect();
+            let floor = floor_for_sender.get(&tx.from).copied().unwrap_or(0);
+
+            let slot = batches
+                .iter()
+                .enumerate()
+                .skip(floor)
+                .find(|(_, batch)| batch.accounts.is_disjoint(&read_write_set))
+                .map(|(i, _)| i);
+
+            let index = match slot {
+                Some(i) => {
+                    batches[i].accounts.extend(read_write_set);
+                    batches[i].txs.push(tx.clone());
+                    i
+                }
+                None => {
+                    batches.push(Batch { txs: vec![tx.clone()], accounts: read_write_set });
+                    batches.len() - 1
+                }
+            };
+
+            floor_for_sender.insert(tx.from, index + 1);
         }
 
-        // Execute groups sequentially, transactions within group can be parallel
+        batches.into_iter().map(|b| b.txs).collect()
+    }
+
+    /// Optimistic-parallel execution: transactions are grouped into
+    /// conflict-free batches by [`Self::partition_into_conflict_free_batches`],
+    /// then every transaction in a batch runs concurrently across rayon's
+    /// thread pool (bridged into `WorldState`'s async API via the current
+    /// tokio `Handle`). `WorldState::transfer` already serializes the
+    /// actual balance mutation behind a single lock, so there's no data
+    /// race to corrupt - but a transaction's nonce not advancing after a
+    /// successful transfer would mean our conflict-freedom assumption was
+    /// wrong, so we re-execute once against current state before trusting
+    /// the receipt, exactly as an optimistic executor's validation pass
+    /// would.
+    pub async fn execute_parallel(&self, transactions: Vec<Transaction>, block_number: u64) -> Vec<TransactionReceipt> {
+        use rayon::prelude::*;
+
+        let batches = Self::partition_into_conflict_free_batches(transactions);
+        let runtime = tokio::runtime::Handle::current();
         let mut all_receipts = Vec::new();
-        for group in groups {
-            for tx in group {
-                let receipt = self.execute(&tx, block_number).await;
-                all_receipts.push(receipt);
-            }
+
+        for batch in batches {
+            let receipts: Vec<TransactionReceipt> = batch
+                .into_par_iter()
+                .map(|tx| {
+                    // Plain transfers always bump the sender's nonce on
+                    // success (see `WorldState::transfer`) - if one didn't,
+                    // some other transaction must have raced `tx.from`
+                    // underneath us despite the batch partitioning, so
+                    // re-execute once against current state rather than
+                    // trust the stale receipt. Contract calls don't touch
+                    // the nonce on this path, and aren't safe to blindly
+                    // re-run, so they skip the check.
+                    let is_plain_transfer = tx.data.is_empty();
+                    let nonce_before = runtime.block_on(self.state.get_nonce(&tx.from));
+                    let receipt = runtime.block_on(self.execute(&tx, block_number));
+
+                    if is_plain_transfer
+                        && receipt.status
+                        && runtime.block_on(self.state.get_nonce(&tx.from)) == nonce_before
+                    {
+                        return runtime.block_on(self.execute(&tx, block_number));
+                    }
+
+                    receipt
+                })
+                .collect();
+            all_receipts.extend(receipts);
         }
 
         all_receipts
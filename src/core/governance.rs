@@ -1,11 +1,58 @@
 use std::collections::HashMap;
 
+/// An exact rational threshold (`numer`/`denom`), compared via integer
+/// cross-multiplication rather than floating point so tallies right at
+/// the boundary aren't subject to rounding bias.
+#[derive(Debug, Clone, Copy)]
+pub struct Ratio {
+    pub numer: u64,
+    pub denom: u64,
+}
+
+/// Approval and quorum bars a proposal must clear to execute. Both are
+/// exact rationals rather than floats for the same reason `Ratio` is.
+#[derive(Debug, Clone, Copy)]
+pub struct GovernanceConfig {
+    /// Fraction of cast votes (`votes_for + votes_against`) that must
+    /// vote `for`, e.g. `2/3` for a supermajority.
+    pub approval_threshold: Ratio,
+    /// Fraction of `total_voting_power` (snapshotted at proposal
+    /// creation) that must have voted at all, e.g. `1/10`.
+    pub quorum: Ratio,
+}
+
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        GovernanceConfig {
+            approval_threshold: Ratio { numer: 1, denom: 2 },
+            quorum: Ratio { numer: 0, denom: 1 },
+        }
+    }
+}
+
+/// Why `execute_proposal` refused to execute a proposal whose tally
+/// wasn't found or already decided another way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GovernanceError {
+    ProposalNotFound,
+    /// Fewer votes were cast than `GovernanceConfig::quorum` of the
+    /// proposal's `total_voting_power` snapshot requires.
+    BelowQuorum,
+    /// Quorum was met, but `votes_for` didn't clear
+    /// `GovernanceConfig::approval_threshold` of votes cast.
+    BelowThreshold,
+}
+
 pub struct Proposal {
     pub id: u64,
     pub title: String,
     pub description: String,
     pub votes_for: u64,
     pub votes_against: u64,
+    /// Total voting power in existence when this proposal was created,
+    /// so quorum is measured against a fixed snapshot rather than
+    /// whatever power exists by the time someone calls `execute_proposal`.
+    pub total_voting_power: u64,
     pub status: ProposalStatus,
 }
 
@@ -19,29 +66,32 @@ pub enum ProposalStatus {
 pub struct Governance {
     proposals: HashMap<u64, Proposal>,
     next_proposal_id: u64,
+    config: GovernanceConfig,
 }
 
 impl Governance {
-    pub fn new() -> Self {
+    pub fn new(config: GovernanceConfig) -> Self {
         Governance {
             proposals: HashMap::new(),
             next_proposal_id: 1,
+            config,
         }
     }
 
-    pub fn create_proposal(&mut self, title: String, description: String) -> u64 {
+    pub fn create_proposal(&mut self, title: String, description: String, total_voting_power: u64) -> u64 {
         let id = self.next_proposal_id;
         self.next_proposal_id += 1;
-        
+
         let proposal = Proposal {
             id,
             title,
             description,
             votes_for: 0,
             votes_against: 0,
+            total_voting_power,
             status: ProposalStatus::Active,
         };
-        
+
         self.proposals.insert(id, proposal);
         id
     }
@@ -63,16 +113,21 @@ impl Governance {
         self. proposals.get(&proposal_id)
     }
 
-    pub fn execute_proposal(&mut self, proposal_id: u64) -> Result<(), String> {
-        if let Some(proposal) = self.proposals.get_mut(&proposal_id) {
-            if proposal.votes_for > proposal.votes_against {
-                proposal.status = ProposalStatus::Executed;
-                Ok(())
-            } else {
-                Err("Proposal not passed".to_string())
-            }
-        } else {
-            Err("Proposal not found".to_string())
+    pub fn execute_proposal(&mut self, proposal_id: u64) -> Result<(), GovernanceError> {
+        let proposal = self.proposals.get_mut(&proposal_id).ok_or(GovernanceError::ProposalNotFound)?;
+
+        let total_cast = proposal.votes_for + proposal.votes_against;
+        let quorum = &self.config.quorum;
+        if total_cast * quorum.denom < proposal.total_voting_power * quorum.numer {
+            return Err(GovernanceError::BelowQuorum);
         }
+
+        let threshold = &self.config.approval_threshold;
+        if proposal.votes_for * threshold.denom < total_cast * threshold.numer {
+            return Err(GovernanceError::BelowThreshold);
+        }
+
+        proposal.status = ProposalStatus::Executed;
+        Ok(())
     }
 }
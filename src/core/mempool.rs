@@ -1,26 +1,141 @@
-use std::collections::{HashMap, VecDeque};
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use super::transaction::Transaction;
 
+/// How many recent block-height slots the processed-status cache keeps
+/// before evicting the oldest, bounding its memory regardless of chain
+/// height.
+const STATUS_CACHE_WINDOW: usize = 64;
+
+/// Rolling record of transactions already included in a recent block,
+/// keyed by the `blake3` hash of their canonical message bytes
+/// (from/to/value/nonce/gas_price/gas_limit/data - not the signature), so
+/// a transaction that was already committed can't be re-admitted to the
+/// mempool. Nonce must be included: two distinct transactions with the
+/// same sender/recipient/amount (e.g. paying the same round amount twice)
+/// would otherwise collide and the second would be rejected as a replay
+/// of the first. Bounded to a window of recent slots the same way
+/// `l2_vm::parallel_execution::StatusCache` bounds its own replay-dedup
+/// window.
+struct StatusCache {
+    seen: HashSet<[u8; 32]>,
+    slots: VecDeque<(u64, Vec<[u8; 32]>)>,
+}
+
+impl StatusCache {
+    fn new() -> Self {
+        StatusCache {
+            seen: HashSet::new(),
+            slots: VecDeque::new(),
+        }
+    }
+
+    fn message_hash(tx: &Transaction) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(tx.from.as_bytes());
+        hasher.update(tx.to.as_bytes());
+        hasher.update(&tx.value.to_le_bytes());
+        hasher.update(&tx.nonce.to_le_bytes());
+        hasher.update(&tx.gas_price.to_le_bytes());
+        hasher.update(&tx.gas_limit.to_le_bytes());
+        hasher.update(&tx.data);
+        *hasher.finalize().as_bytes()
+    }
+
+    fn contains(&self, hash: &[u8; 32]) -> bool {
+        self.seen.contains(hash)
+    }
+
+    fn register(&mut self, hashes: Vec<[u8; 32]>, slot: u64) {
+        for hash in &hashes {
+            self.seen.insert(*hash);
+        }
+
+        match self.slots.back_mut() {
+            Some((s, existing)) if *s == slot => existing.extend(hashes),
+            _ => self.slots.push_back((slot, hashes)),
+        }
+
+        while self.slots.len() > STATUS_CACHE_WINDOW {
+            if let Some((_, evicted)) = self.slots.pop_front() {
+                for hash in evicted {
+                    self.seen.remove(&hash);
+                }
+            }
+        }
+    }
+}
+
+/// A pending transaction's position in the priority ordering: highest
+/// `gas_price` first, ties broken by `seq` (arrival order) so two nodes
+/// fed the same transactions in the same order always select and order
+/// an identical set - required for deterministic block building.
+#[derive(Clone, Eq, PartialEq)]
+struct PendingKey {
+    gas_price: u64,
+    seq: u64,
+    hash: String,
+}
+
+impl Ord for PendingKey {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        other.gas_price.cmp(&self.gas_price)
+            .then_with(|| self.seq.cmp(&other.seq))
+    }
+}
+
+impl PartialOrd for PendingKey {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
 pub struct Mempool {
-    pending: Arc<RwLock<VecDeque<Transaction>>>,
+    /// Ordered by `PendingKey`, so iterating front-to-back yields
+    /// transactions highest-gas-price-first.
+    pending: Arc<RwLock<BTreeSet<PendingKey>>>,
     by_hash: Arc<RwLock<HashMap<String, Transaction>>>,
+    /// `tx.hash -> PendingKey`, so `remove_transaction` can remove the
+    /// exact set entry in O(log n) instead of scanning for it.
+    index: Arc<RwLock<HashMap<String, PendingKey>>>,
+    next_seq: Arc<AtomicU64>,
+    status_cache: Arc<RwLock<StatusCache>>,
+    /// Nanoseconds the most recent `add_transaction` call spent on its
+    /// status-cache lookup, measured separately from time spent waiting
+    /// on the `pending`/`by_hash` locks.
+    last_check_time_ns: Arc<AtomicU64>,
     max_size: usize,
 }
 
 impl Mempool {
     pub fn new(max_size: usize) -> Self {
         Mempool {
-            pending: Arc::new(RwLock::new(VecDeque::new())),
+            pending: Arc::new(RwLock::new(BTreeSet::new())),
             by_hash: Arc::new(RwLock::new(HashMap::new())),
+            index: Arc::new(RwLock::new(HashMap::new())),
+            next_seq: Arc::new(AtomicU64::new(0)),
+            status_cache: Arc::new(RwLock::new(StatusCache::new())),
+            last_check_time_ns: Arc::new(AtomicU64::new(0)),
             max_size,
         }
     }
 
     pub async fn add_transaction(&self, tx: Transaction) -> Result<(), String> {
+        let check_start = std::time::Instant::now();
+        let message_hash = StatusCache::message_hash(&tx);
+        let already_processed = self.status_cache.read().await.contains(&message_hash);
+        self.last_check_time_ns.store(check_start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+
+        if already_processed {
+            return Err("Transaction already processed in a recent block".to_string());
+        }
+
         let mut pending = self.pending.write().await;
         let mut by_hash = self.by_hash.write().await;
+        let mut index = self.index.write().await;
 
         if pending.len() >= self.max_size {
             return Err("Mempool full".to_string());
@@ -30,18 +145,91 @@ impl Mempool {
             return Err("Transaction already exists".to_string());
         }
 
-        by_hash.insert(tx.hash.clone(), tx. clone());
-        pending.push_back(tx);
-        
+        let key = PendingKey {
+            gas_price: tx.gas_price,
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            hash: tx.hash.clone(),
+        };
+
+        index.insert(tx.hash.clone(), key.clone());
+        pending.insert(key);
+        by_hash.insert(tx.hash.clone(), tx);
+
         Ok(())
     }
 
+    /// Records `hashes` (each a `StatusCache::message_hash` of a
+    /// transaction included in the block just produced for `slot`) so a
+    /// later re-submission of the same transaction is rejected by
+    /// `add_transaction` instead of being processed again. Evicts the
+    /// oldest slot once the rolling window is exceeded.
+    pub async fn register_processed(&self, hashes: Vec<[u8; 32]>, slot: u64) {
+        self.status_cache.write().await.register(hashes, slot);
+    }
+
+    /// `blake3` message hash `register_processed` expects, exposed so
+    /// callers (e.g. the block producer) can compute it for transactions
+    /// they're about to commit.
+    pub fn message_hash(tx: &Transaction) -> [u8; 32] {
+        StatusCache::message_hash(tx)
+    }
+
+    /// Time the most recent `add_transaction` call spent checking the
+    /// processed-status cache, in nanoseconds - isolated from
+    /// lock-acquisition/insert cost so operators can see dedup overhead
+    /// on its own.
+    pub fn check_time_ns(&self) -> u64 {
+        self.last_check_time_ns.load(Ordering::Relaxed)
+    }
+
+    /// The `count` highest-paying pending transactions, highest first,
+    /// ties broken by arrival order.
     pub async fn get_transactions(&self, count: usize) -> Vec<Transaction> {
         let mut pending = self.pending.write().await;
+        let mut by_hash = self.by_hash.write().await;
+        let mut index = self.index.write().await;
         let mut result = Vec::new();
 
-        for _ in 0..count. min(pending.len()) {
-            if let Some(tx) = pending.pop_front() {
+        for _ in 0..count {
+            let Some(key) = pending.iter().next().cloned() else { break };
+            pending.remove(&key);
+            index.remove(&key.hash);
+            if let Some(tx) = by_hash.remove(&key.hash) {
+                result.push(tx);
+            }
+        }
+
+        result
+    }
+
+    /// Greedily fills a block's gas budget: walks pending transactions
+    /// highest-price-first, taking each one whose `gas_limit` still fits
+    /// the remaining budget and skipping (leaving in the pool) any that
+    /// don't, so a single oversized transaction doesn't block smaller,
+    /// lower-priority ones from still being packed in.
+    pub async fn get_transactions_up_to_gas(&self, gas_limit: u64) -> Vec<Transaction> {
+        let mut pending = self.pending.write().await;
+        let mut by_hash = self.by_hash.write().await;
+        let mut index = self.index.write().await;
+        let mut remaining = gas_limit;
+
+        let taken: Vec<PendingKey> = pending
+            .iter()
+            .filter(|key| {
+                let fits = by_hash.get(&key.hash).map_or(false, |tx| tx.gas_limit <= remaining);
+                if fits {
+                    remaining -= by_hash[&key.hash].gas_limit;
+                }
+                fits
+            })
+            .cloned()
+            .collect();
+
+        let mut result = Vec::with_capacity(taken.len());
+        for key in taken {
+            pending.remove(&key);
+            index.remove(&key.hash);
+            if let Some(tx) = by_hash.remove(&key.hash) {
                 result.push(tx);
             }
         }
@@ -53,8 +241,26 @@ impl Mempool {
         self.pending.read().await.len()
     }
 
+    /// Sum of `gas_price * gas_limit` across every currently pending
+    /// transaction - the total fee being offered, used as "routing work
+    /// collected" by fee-aware block-bundling policies.
+    pub async fn total_pending_fees(&self) -> u64 {
+        self.by_hash
+            .read()
+            .await
+            .values()
+            .map(|tx| tx.gas_price.saturating_mul(tx.gas_limit))
+            .sum()
+    }
+
     pub async fn remove_transaction(&self, hash: &str) {
-        let mut by_hash = self.by_hash.write(). await;
-        by_hash. remove(hash);
+        let mut by_hash = self.by_hash.write().await;
+        let mut pending = self.pending.write().await;
+        let mut index = self.index.write().await;
+
+        by_hash.remove(hash);
+        if let Some(key) = index.remove(hash) {
+            pending.remove(&key);
+        }
     }
 }
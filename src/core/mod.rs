@@ -1,12 +1,17 @@
 pub mod transaction;
+pub mod tx_decode;
 pub mod block;
+pub mod block_store;
 pub mod state;
+pub mod trie;
+pub mod snapshot;
 pub mod mempool;
 pub mod executor;
 pub mod benchmark;
 
 pub use transaction::{Transaction, TransactionReceipt};
-pub use block::{Block, BlockHeader};
+pub use block::{Block, BlockHeader, BlockWeights, ClassWeight, ClassConsumed, GasClass};
+pub use block_store::{BlockProvider, InMemoryBlockStore, SqliteBlockStore};
 pub use state::{Account, WorldState};
 pub use mempool::Mempool;
 pub use executor::TransactionExecutor;
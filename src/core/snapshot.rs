@@ -0,0 +1,239 @@
+// Snapshot / warp-sync subsystem: lets a fresh node bootstrap from a
+// point-in-time export of WorldState instead of replaying every block.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use super::state::{Account, WorldState};
+
+pub const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRecord {
+    pub address: String,
+    pub balance: u64,
+    pub nonce: u64,
+}
+
+/// Describes a snapshot without carrying the account data itself, so it can
+/// be exchanged cheaply before any chunk transfer happens.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Manifest {
+    pub state_root: String,
+    pub block_number: u64,
+    pub block_hash: String,
+    pub chunk_hashes: Vec<String>,
+}
+
+impl Manifest {
+    pub fn hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("manifest serialization cannot fail");
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotChunk {
+    pub accounts: Vec<AccountRecord>,
+}
+
+impl SnapshotChunk {
+    pub fn hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("chunk serialization cannot fail");
+        let mut hasher = Sha256::new();
+        hasher.update(&bytes);
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Partitions the current account set into fixed-size chunks and builds the
+/// manifest describing them.
+pub async fn export(
+    state: &WorldState,
+    block_number: u64,
+    block_hash: String,
+    chunk_size: usize,
+) -> (Manifest, Vec<SnapshotChunk>) {
+    let mut accounts = state.all_accounts().await;
+    accounts.sort_by(|a, b| a.address.cmp(&b.address));
+
+    let chunks: Vec<SnapshotChunk> = accounts
+        .chunks(chunk_size.max(1))
+        .map(|slice| SnapshotChunk {
+            accounts: slice
+                .iter()
+                .map(|a| AccountRecord {
+                    address: a.address.clone(),
+                    balance: a.balance,
+                    nonce: a.nonce,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let chunk_hashes = chunks.iter().map(|c| c.hash()).collect();
+
+    let manifest = Manifest {
+        state_root: state.state_root().await,
+        block_number,
+        block_hash,
+        chunk_hashes,
+    };
+
+    (manifest, chunks)
+}
+
+/// Drives a restore of `WorldState` against a manifest, one chunk at a
+/// time. A chunk is only considered consumed once its hash has been
+/// verified against the manifest's outstanding set, so a dropped or
+/// corrupted chunk simply stays pending and can be re-requested.
+pub struct Importer {
+    manifest: Manifest,
+    pending: HashSet<String>,
+}
+
+impl Importer {
+    pub fn new(manifest: Manifest) -> Self {
+        let pending = manifest.chunk_hashes.iter().cloned().collect();
+        Importer { manifest, pending }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Verify the chunk hash against the outstanding set, apply its
+    /// accounts, and remove it from `pending` only on success.
+    pub async fn apply_chunk(&mut self, state: &WorldState, chunk: SnapshotChunk) -> Result<(), String> {
+        let hash = chunk.hash();
+
+        if !self.pending.contains(&hash) {
+            return Err(format!("chunk {} is not an outstanding part of this manifest", hash));
+        }
+
+        for record in &chunk.accounts {
+            state
+                .restore_account(Account {
+                    address: record.address.clone(),
+                    balance: record.balance,
+                    nonce: record.nonce,
+                    code: vec![],
+                    storage: std::collections::HashMap::new(),
+                })
+                .await;
+        }
+
+        self.pending.remove(&hash);
+
+        Ok(())
+    }
+
+    /// Once every chunk has landed, confirm the restored root matches the
+    /// manifest. A mismatch means the whole restore is discarded by the
+    /// caller - there is no partial-success state to fall back to.
+    pub async fn finalize(&self, state: &WorldState) -> Result<(), String> {
+        if !self.is_complete() {
+            return Err(format!("{} chunk(s) still pending", self.remaining()));
+        }
+
+        let root = state.state_root().await;
+        if root != self.manifest.state_root {
+            return Err(format!(
+                "state root mismatch after restore: expected {}, got {}",
+                self.manifest.state_root, root
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Manifests that failed verification, so a node never re-attempts a known
+/// bad source.
+#[derive(Default)]
+pub struct ManifestBlacklist {
+    bad: HashSet<String>,
+}
+
+impl ManifestBlacklist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_blacklisted(&self, manifest: &Manifest) -> bool {
+        self.bad.contains(&manifest.hash())
+    }
+
+    pub fn blacklist(&mut self, manifest: &Manifest) {
+        self.bad.insert(manifest.hash());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn export_then_import_reproduces_state_root() {
+        let source = WorldState::new();
+        source.create_account("0xaaa".to_string(), 100).await;
+        source.create_account("0xbbb".to_string(), 200).await;
+        source.create_account("0xccc".to_string(), 300).await;
+
+        let (manifest, chunks) = export(&source, 1, "0xblockhash".to_string(), 2).await;
+        assert_eq!(manifest.chunk_hashes.len(), 2);
+
+        let target = WorldState::new();
+        let mut importer = Importer::new(manifest.clone());
+
+        for chunk in chunks {
+            importer.apply_chunk(&target, chunk).await.unwrap();
+        }
+
+        assert!(importer.is_complete());
+        importer.finalize(&target).await.unwrap();
+        assert_eq!(target.state_root().await, manifest.state_root);
+    }
+
+    #[tokio::test]
+    async fn dropped_chunk_stays_pending() {
+        let source = WorldState::new();
+        source.create_account("0xaaa".to_string(), 100).await;
+        source.create_account("0xbbb".to_string(), 200).await;
+
+        let (manifest, chunks) = export(&source, 1, "0xblockhash".to_string(), 1).await;
+        assert_eq!(chunks.len(), 2);
+
+        let target = WorldState::new();
+        let mut importer = Importer::new(manifest);
+
+        // Only the first chunk arrives - the second is "dropped".
+        importer.apply_chunk(&target, chunks[0].clone()).await.unwrap();
+
+        assert!(!importer.is_complete());
+        assert_eq!(importer.remaining(), 1);
+        assert!(importer.finalize(&target).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn bad_manifest_gets_blacklisted() {
+        let source = WorldState::new();
+        source.create_account("0xaaa".to_string(), 100).await;
+
+        let (mut manifest, _chunks) = export(&source, 1, "0xblockhash".to_string(), 10).await;
+        manifest.state_root = "tampered".to_string();
+
+        let mut blacklist = ManifestBlacklist::new();
+        assert!(!blacklist.is_blacklisted(&manifest));
+
+        blacklist.blacklist(&manifest);
+        assert!(blacklist.is_blacklisted(&manifest));
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
+use super::trie::{self, MerkleProof, MerkleTree};
 
 #[derive(Debug, Clone)]
 pub struct Account {
@@ -25,18 +27,71 @@ impl Account {
 
 pub struct WorldState {
     accounts: Arc<RwLock<HashMap<String, Account>>>,
+    state_root: Arc<RwLock<[u8; 32]>>,
 }
 
 impl WorldState {
     pub fn new() -> Self {
         WorldState {
             accounts: Arc::new(RwLock::new(HashMap::new())),
+            state_root: Arc::new(RwLock::new(trie::ZERO_ROOT)),
         }
     }
 
     pub async fn create_account(&self, address: String, balance: u64) {
         let mut accounts = self. accounts.write().await;
         accounts.insert(address. clone(), Account::new(address, balance));
+        drop(accounts);
+        self.recompute_root().await;
+    }
+
+    /// Leaf hash for a single account: sha256(address || balance_le || nonce_le).
+    fn account_leaf(account: &Account) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(account.address.as_bytes());
+        hasher.update(account.balance.to_le_bytes());
+        hasher.update(account.nonce.to_le_bytes());
+        let out = hasher.finalize();
+        let mut leaf = [0u8; 32];
+        leaf.copy_from_slice(&out);
+        leaf
+    }
+
+    /// Accounts sorted by address - the deterministic leaf ordering the
+    /// trie is built over.
+    fn sorted_addresses(accounts: &HashMap<String, Account>) -> Vec<&String> {
+        let mut addresses: Vec<&String> = accounts.keys().collect();
+        addresses.sort();
+        addresses
+    }
+
+    fn build_trie(accounts: &HashMap<String, Account>) -> MerkleTree {
+        let addresses = Self::sorted_addresses(accounts);
+        let leaves = addresses.iter().map(|addr| Self::account_leaf(&accounts[*addr])).collect();
+        MerkleTree::from_leaves(leaves)
+    }
+
+    async fn recompute_root(&self) {
+        let accounts = self.accounts.read().await;
+        let root = Self::build_trie(&accounts).root();
+        *self.state_root.write().await = root;
+    }
+
+    /// Current Merkle state root, as a hex string.
+    pub async fn state_root(&self) -> String {
+        trie::hex_encode(&*self.state_root.read().await)
+    }
+
+    /// Account plus a membership proof against the current state root.
+    pub async fn get_proof(&self, address: &str) -> Option<(Account, MerkleProof)> {
+        let accounts = self.accounts.read().await;
+        let addresses = Self::sorted_addresses(&accounts);
+        let index = addresses.iter().position(|addr| addr.as_str() == address)?;
+
+        let leaves = addresses.iter().map(|addr| Self::account_leaf(&accounts[*addr])).collect();
+        let proof = MerkleTree::from_leaves(leaves).proof(index)?;
+
+        Some((accounts.get(address)?.clone(), proof))
     }
 
     pub async fn get_balance(&self, address: &str) -> Option<u64> {
@@ -63,6 +118,9 @@ impl WorldState {
             .and_modify(|acc| acc.balance += amount)
             .or_insert_with(|| Account::new(to.to_string(), amount));
 
+        drop(accounts);
+        self.recompute_root().await;
+
         Ok(())
     }
 
@@ -70,4 +128,50 @@ impl WorldState {
         let accounts = self.accounts.read().await;
         accounts.get(address). map(|acc| acc.nonce).unwrap_or(0)
     }
+
+    /// Full account snapshot, for callers (e.g. the contract dispatch path
+    /// in `TransactionExecutor`) that need more than balance or nonce.
+    pub async fn get_account(&self, address: &str) -> Option<Account> {
+        self.accounts.read().await.get(address).cloned()
+    }
+
+    /// Overwrite an account's persistent storage map after a contract call.
+    /// Storage isn't part of `account_leaf`, so this doesn't touch the
+    /// state root.
+    pub async fn set_storage(&self, address: &str, storage: HashMap<String, String>) {
+        if let Some(account) = self.accounts.write().await.get_mut(address) {
+            account.storage = storage;
+        }
+    }
+
+    /// Deploy (or overwrite) an account's wasm bytecode.
+    pub async fn set_code(&self, address: &str, code: Vec<u8>) {
+        let mut accounts = self.accounts.write().await;
+        accounts
+            .entry(address.to_string())
+            .and_modify(|acc| acc.code = code.clone())
+            .or_insert_with(|| {
+                let mut account = Account::new(address.to_string(), 0);
+                account.code = code;
+                account
+            });
+        drop(accounts);
+        self.recompute_root().await;
+    }
+
+    /// Snapshot of every account, in no particular order - callers that
+    /// need determinism (e.g. snapshot export) should sort by address.
+    pub async fn all_accounts(&self) -> Vec<Account> {
+        self.accounts.read().await.values().cloned().collect()
+    }
+
+    /// Insert (or overwrite) an account exactly as given, recomputing the
+    /// root afterwards. Used by snapshot/warp-sync restore, where balance
+    /// and nonce must be set atomically rather than derived incrementally.
+    pub async fn restore_account(&self, account: Account) {
+        let mut accounts = self.accounts.write().await;
+        accounts.insert(account.address.clone(), account);
+        drop(accounts);
+        self.recompute_root().await;
+    }
 }
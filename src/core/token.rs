@@ -1,24 +1,51 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
 
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A hashed-timelock lock on an escrowed token balance, following the
+/// xmr-btc atomic-swap construction: `maker` can `refund_htlc` once
+/// `timelock` (unix seconds) passes, otherwise whoever reveals a preimage
+/// of `hashlock` via `redeem_htlc` claims the balance for `taker`.
+#[derive(Debug, Clone)]
+pub struct HtlcLock {
+    pub id: String,
+    pub hashlock: [u8; 32],
+    pub timelock: u64,
+    pub maker: String,
+    pub taker: String,
+    pub asset: String,
+    pub amount: u64,
+    pub redeemed: bool,
+    pub refunded: bool,
+}
+
 pub struct ERC20Token {
     name: String,
     symbol: String,
     total_supply: u64,
     balances: Arc<RwLock<HashMap<String, u64>>>,
+    htlcs: Arc<RwLock<HashMap<String, HtlcLock>>>,
 }
 
 impl ERC20Token {
     pub fn new(name: String, symbol: String, total_supply: u64) -> Self {
         let mut balances = HashMap::new();
         balances.insert("0x0000000000000000000000000000000000000000".to_string(), total_supply);
-        
+
         ERC20Token {
             name,
             symbol,
             total_supply,
             balances: Arc::new(RwLock::new(balances)),
+            htlcs: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -53,31 +80,158 @@ impl ERC20Token {
     pub fn total_supply(&self) -> u64 {
         self.total_supply
     }
+
+    /// Escrows `amount` out of `maker`'s balance under `hashlock`,
+    /// refundable by `maker` after `timelock` and redeemable by anyone who
+    /// reveals a matching preimage before then - the token side of a
+    /// trustless NFT-for-token or cross-chain swap.
+    pub async fn lock_htlc(&self, maker: String, taker: String, amount: u64, hashlock: [u8; 32], timelock: u64) -> Result<String, String> {
+        let mut balances = self.balances.write().await;
+        let maker_balance = *balances.get(&maker).unwrap_or(&0);
+        if maker_balance < amount {
+            return Err("Insufficient balance".to_string());
+        }
+
+        let lock_id = format!("htlc_{}", hashlock.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+        let mut htlcs = self.htlcs.write().await;
+        if htlcs.contains_key(&lock_id) {
+            return Err("a lock already exists for this hashlock".to_string());
+        }
+
+        balances.insert(maker.clone(), maker_balance - amount);
+        htlcs.insert(lock_id.clone(), HtlcLock {
+            id: lock_id.clone(),
+            hashlock,
+            timelock,
+            maker,
+            taker,
+            asset: self.symbol.clone(),
+            amount,
+            redeemed: false,
+            refunded: false,
+        });
+
+        Ok(lock_id)
+    }
+
+    /// Reveals `preimage`; if `sha256(preimage) == hashlock` and the
+    /// timelock hasn't expired, credits the escrowed amount to the taker
+    /// and hands the preimage back so the counterparty's leg of the swap
+    /// can be redeemed with the same secret.
+    pub async fn redeem_htlc(&self, lock_id: &str, preimage: &[u8]) -> Result<Vec<u8>, String> {
+        let (taker, amount) = {
+            let mut htlcs = self.htlcs.write().await;
+            let lock = htlcs.get_mut(lock_id).ok_or("no such HTLC lock")?;
+
+            if lock.redeemed || lock.refunded {
+                return Err("lock already settled".to_string());
+            }
+            if now_secs() >= lock.timelock {
+                return Err("timelock has expired, only refund is allowed now".to_string());
+            }
+
+            let mut hasher = Sha256::new();
+            hasher.update(preimage);
+            let hash: [u8; 32] = hasher.finalize().into();
+            if hash != lock.hashlock {
+                return Err("preimage does not match hashlock".to_string());
+            }
+
+            lock.redeemed = true;
+            (lock.taker.clone(), lock.amount)
+        };
+
+        let mut balances = self.balances.write().await;
+        let taker_balance = *balances.get(&taker).unwrap_or(&0);
+        balances.insert(taker, taker_balance + amount);
+
+        Ok(preimage.to_vec())
+    }
+
+    /// Returns the escrowed amount to `maker` once the timelock has
+    /// expired - guards against a counterparty who never reveals the
+    /// preimage.
+    pub async fn refund_htlc(&self, lock_id: &str) -> Result<(), String> {
+        let (maker, amount) = {
+            let mut htlcs = self.htlcs.write().await;
+            let lock = htlcs.get_mut(lock_id).ok_or("no such HTLC lock")?;
+
+            if lock.redeemed || lock.refunded {
+                return Err("lock already settled".to_string());
+            }
+            if now_secs() < lock.timelock {
+                return Err("timelock has not expired yet".to_string());
+            }
+
+            lock.refunded = true;
+            (lock.maker.clone(), lock.amount)
+        };
+
+        let mut balances = self.balances.write().await;
+        let maker_balance = *balances.get(&maker).unwrap_or(&0);
+        balances.insert(maker, maker_balance + amount);
+
+        Ok(())
+    }
+}
+
+/// NEP-177-style metadata - both the contract-level metadata passed to
+/// `ERC721Token::new` and the per-token metadata passed to `mint`.
+#[derive(Debug, Clone, Default)]
+pub struct TokenMetadata {
+    pub name: String,
+    pub description: String,
+    pub media_uri: String,
+    pub attributes: HashMap<String, String>,
 }
 
 pub struct ERC721Token {
     name: String,
     symbol: String,
+    contract_metadata: TokenMetadata,
     owners: Arc<RwLock<HashMap<u64, String>>>,
+    approvals: Arc<RwLock<HashMap<u64, String>>>,
+    token_metadata: Arc<RwLock<HashMap<u64, TokenMetadata>>>,
+    // Transfer/approval events, NEP-171 style, queued for a caller (e.g.
+    // `TransactionExecutor`) to drain into `TransactionReceipt.logs`.
+    events: Arc<RwLock<Vec<String>>>,
 }
 
 impl ERC721Token {
-    pub fn new(name: String, symbol: String) -> Self {
+    pub fn new(name: String, symbol: String, contract_metadata: TokenMetadata) -> Self {
         ERC721Token {
             name,
             symbol,
+            contract_metadata,
             owners: Arc::new(RwLock::new(HashMap::new())),
+            approvals: Arc::new(RwLock::new(HashMap::new())),
+            token_metadata: Arc::new(RwLock::new(HashMap::new())),
+            events: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
-    pub async fn mint(&self, token_id: u64, owner: String) -> Result<bool, String> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn contract_metadata(&self) -> &TokenMetadata {
+        &self.contract_metadata
+    }
+
+    pub async fn mint(&self, token_id: u64, owner: String, metadata: TokenMetadata) -> Result<bool, String> {
         let mut owners = self.owners.write(). await;
-        
+
         if owners.contains_key(&token_id) {
             return Err("Token already exists".to_string());
         }
-        
-        owners.insert(token_id, owner);
+
+        owners.insert(token_id, owner.clone());
+        self.token_metadata.write().await.insert(token_id, metadata);
+        self.emit(format!("Transfer(from=0x0, to={}, token_id={})", owner, token_id)).await;
         Ok(true)
     }
 
@@ -86,14 +240,214 @@ impl ERC721Token {
         owners.get(&token_id).cloned()
     }
 
+    pub async fn token_metadata(&self, token_id: u64) -> Option<TokenMetadata> {
+        self.token_metadata.read().await.get(&token_id).cloned()
+    }
+
+    /// NEP-171 `nft_approve`-equivalent: only the owner can grant another
+    /// address the right to move `token_id` via `transfer_from`.
+    pub async fn approve(&self, token_id: u64, owner: &str, approved: String) -> Result<(), String> {
+        self.require_owner(token_id, owner).await?;
+        self.approvals.write().await.insert(token_id, approved.clone());
+        self.emit(format!("Approval(owner={}, approved={}, token_id={})", owner, approved, token_id)).await;
+        Ok(())
+    }
+
+    /// Clears any standing approval on `token_id`.
+    pub async fn revoke(&self, token_id: u64, owner: &str) -> Result<(), String> {
+        self.require_owner(token_id, owner).await?;
+        self.approvals.write().await.remove(&token_id);
+        Ok(())
+    }
+
+    pub async fn is_approved(&self, token_id: u64, spender: &str) -> bool {
+        self.approvals.read().await.get(&token_id).map(|approved| approved == spender).unwrap_or(false)
+    }
+
+    /// Unconditional transfer, kept for existing callers that already
+    /// enforce ownership themselves. New callers should prefer
+    /// `transfer_from`, which checks ownership/approval itself.
     pub async fn transfer(&self, token_id: u64, to: String) -> Result<bool, String> {
         let mut owners = self. owners.write().await;
-        
+
         if !owners.contains_key(&token_id) {
             return Err("Token doesn't exist".to_string());
         }
-        
-        owners.insert(token_id, to);
+
+        let from = owners.insert(token_id, to.clone()).unwrap();
+        drop(owners);
+        self.approvals.write().await.remove(&token_id);
+        self.emit(format!("Transfer(from={}, to={}, token_id={})", from, to, token_id)).await;
+        Ok(true)
+    }
+
+    /// NEP-171 `nft_transfer`-equivalent: `spender` must be the current
+    /// owner or the address `approve` granted.
+    pub async fn transfer_from(&self, token_id: u64, spender: &str, to: String) -> Result<bool, String> {
+        let mut owners = self.owners.write().await;
+        let current_owner = owners.get(&token_id).cloned().ok_or("Token doesn't exist".to_string())?;
+
+        let approved = self.approvals.read().await.get(&token_id).cloned();
+        if spender != current_owner && Some(spender.to_string()) != approved {
+            return Err("caller is neither the owner nor approved".to_string());
+        }
+
+        owners.insert(token_id, to.clone());
+        drop(owners);
+        self.approvals.write().await.remove(&token_id);
+        self.emit(format!("Transfer(from={}, to={}, token_id={})", current_owner, to, token_id)).await;
         Ok(true)
     }
+
+    pub async fn total_supply(&self) -> u64 {
+        self.owners.read().await.len() as u64
+    }
+
+    /// All minted token ids in order, `offset`/`limit` paginated - mirrors
+    /// NEP-181's `nft_tokens`.
+    pub async fn tokens(&self, offset: usize, limit: usize) -> Vec<u64> {
+        let owners = self.owners.read().await;
+        let mut ids: Vec<u64> = owners.keys().copied().collect();
+        ids.sort();
+        ids.into_iter().skip(offset).take(limit).collect()
+    }
+
+    /// Token ids owned by `owner`, `offset`/`limit` paginated - mirrors
+    /// NEP-181's `nft_tokens_for_owner`.
+    pub async fn tokens_for_owner(&self, owner: &str, offset: usize, limit: usize) -> Vec<u64> {
+        let owners = self.owners.read().await;
+        let mut ids: Vec<u64> = owners.iter().filter(|(_, o)| o.as_str() == owner).map(|(id, _)| *id).collect();
+        ids.sort();
+        ids.into_iter().skip(offset).take(limit).collect()
+    }
+
+    async fn require_owner(&self, token_id: u64, caller: &str) -> Result<(), String> {
+        match self.owners.read().await.get(&token_id) {
+            Some(owner) if owner == caller => Ok(()),
+            Some(_) => Err("caller is not the token owner".to_string()),
+            None => Err("Token doesn't exist".to_string()),
+        }
+    }
+
+    async fn emit(&self, event: String) {
+        self.events.write().await.push(event);
+    }
+
+    /// Drain accumulated transfer/approval events so a caller (e.g.
+    /// `TransactionExecutor`) can fold them into `TransactionReceipt.logs`.
+    pub async fn drain_events(&self) -> Vec<String> {
+        std::mem::take(&mut *self.events.write().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token() -> ERC721Token {
+        ERC721Token::new("Nusa Punks".to_string(), "NPUNK".to_string(), TokenMetadata::default())
+    }
+
+    fn erc20() -> ERC20Token {
+        ERC20Token::new("Nusa".to_string(), "NUSA".to_string(), 1_000_000)
+    }
+
+    async fn fund(token: &ERC20Token, address: &str, amount: u64) {
+        token
+            .transfer("0x0000000000000000000000000000000000000000", address, amount)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn erc20_htlc_redeem_with_the_right_preimage_credits_the_taker() {
+        let token = erc20();
+        fund(&token, "alice", 100).await;
+
+        let preimage = b"super-secret".to_vec();
+        let hashlock: [u8; 32] = Sha256::digest(&preimage).into();
+        let lock_id = token
+            .lock_htlc("alice".to_string(), "bob".to_string(), 40, hashlock, now_secs() + 3600)
+            .await
+            .unwrap();
+
+        assert_eq!(token.balance_of("alice").await, 60);
+        assert_eq!(token.redeem_htlc(&lock_id, &preimage).await.unwrap(), preimage);
+        assert_eq!(token.balance_of("bob").await, 40);
+    }
+
+    #[tokio::test]
+    async fn erc20_htlc_redeem_with_the_wrong_preimage_is_rejected() {
+        let token = erc20();
+        fund(&token, "alice", 100).await;
+
+        let hashlock: [u8; 32] = Sha256::digest(b"correct-secret").into();
+        let lock_id = token
+            .lock_htlc("alice".to_string(), "bob".to_string(), 40, hashlock, now_secs() + 3600)
+            .await
+            .unwrap();
+
+        assert!(token.redeem_htlc(&lock_id, b"wrong-secret").await.is_err());
+        assert_eq!(token.balance_of("bob").await, 0);
+    }
+
+    #[tokio::test]
+    async fn erc20_htlc_refund_before_expiry_is_rejected_but_allowed_once_expired() {
+        let token = erc20();
+        fund(&token, "alice", 100).await;
+
+        let unexpired = token
+            .lock_htlc("alice".to_string(), "bob".to_string(), 40, Sha256::digest(b"secret-a").into(), now_secs() + 3600)
+            .await
+            .unwrap();
+        assert!(token.refund_htlc(&unexpired).await.is_err());
+
+        // Timelock of 0 is already in the past relative to any real clock.
+        let expired = token
+            .lock_htlc("alice".to_string(), "bob".to_string(), 20, Sha256::digest(b"secret-b").into(), 0)
+            .await
+            .unwrap();
+        assert!(token.refund_htlc(&expired).await.is_ok());
+        // The refunded 20 is back; 40 stays escrowed in the unexpired lock.
+        assert_eq!(token.balance_of("alice").await, 60);
+    }
+
+    #[tokio::test]
+    async fn mint_emits_a_transfer_event_and_sets_metadata() {
+        let nft = token();
+        let metadata = TokenMetadata { name: "Punk #1".to_string(), ..Default::default() };
+        assert!(nft.mint(1, "alice".to_string(), metadata.clone()).await.unwrap());
+
+        assert_eq!(nft.owner_of(1).await, Some("alice".to_string()));
+        assert_eq!(nft.token_metadata(1).await.unwrap().name, "Punk #1");
+        assert_eq!(nft.drain_events().await, vec!["Transfer(from=0x0, to=alice, token_id=1)"]);
+    }
+
+    #[tokio::test]
+    async fn transfer_from_requires_ownership_or_approval() {
+        let nft = token();
+        nft.mint(1, "alice".to_string(), TokenMetadata::default()).await.unwrap();
+
+        assert!(nft.transfer_from(1, "mallory", "mallory".to_string()).await.is_err());
+
+        nft.approve(1, "alice", "bob".to_string()).await.unwrap();
+        assert!(nft.is_approved(1, "bob").await);
+        assert!(nft.transfer_from(1, "bob", "bob".to_string()).await.unwrap());
+        assert_eq!(nft.owner_of(1).await, Some("bob".to_string()));
+
+        // Approval is cleared by the transfer it was used for.
+        assert!(!nft.is_approved(1, "bob").await);
+    }
+
+    #[tokio::test]
+    async fn enumeration_paginates_by_owner_and_overall() {
+        let nft = token();
+        nft.mint(1, "alice".to_string(), TokenMetadata::default()).await.unwrap();
+        nft.mint(2, "alice".to_string(), TokenMetadata::default()).await.unwrap();
+        nft.mint(3, "bob".to_string(), TokenMetadata::default()).await.unwrap();
+
+        assert_eq!(nft.total_supply().await, 3);
+        assert_eq!(nft.tokens(1, 10).await, vec![2, 3]);
+        assert_eq!(nft.tokens_for_owner("alice", 0, 1).await, vec![1]);
+    }
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
+use super::block::GasClass;
+use super::tx_decode;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
@@ -8,6 +10,7 @@ pub struct Transaction {
     pub value: u64,
     pub gas_price: u64,
     pub gas_limit: u64,
+    pub gas_class: GasClass,
     pub nonce: u64,
     pub data: Vec<u8>,
     pub signature: Vec<u8>,
@@ -22,6 +25,7 @@ impl Transaction {
             value,
             gas_price: 1_000_000_000, // 1 Gwei
             gas_limit: 21000,
+            gas_class: GasClass::Normal,
             nonce,
             data: vec![],
             signature: vec![],
@@ -39,8 +43,38 @@ impl Transaction {
     }
 
     pub fn verify(&self) -> bool {
-        // Simplified verification
-        ! self.from.is_empty() && !self.to.is_empty() && self.value > 0
+        // Simplified verification. `value == 0` is valid - contract calls
+        // routed through `NusaVM` carry no value on the common path.
+        ! self.from.is_empty() && !self.to.is_empty()
+    }
+
+    /// Decode a raw RLP-encoded signed transaction, recover the sender via
+    /// ECDSA (EIP-155 aware), and stamp it with the real keccak256 tx hash.
+    /// Does not check the sender's account nonce - callers have access to
+    /// `WorldState` and should compare `tx.nonce` themselves before
+    /// admitting the transaction to the mempool.
+    pub fn from_raw(raw: &[u8], chain_id: u64) -> Result<Transaction, String> {
+        let decoded = tx_decode::decode_raw_transaction(raw)?;
+        let hash = tx_decode::signing_hash(&decoded, chain_id);
+        let sender = tx_decode::recover_sender(&decoded, hash)?;
+
+        let mut signature = Vec::with_capacity(65);
+        signature.extend_from_slice(&decoded.r);
+        signature.extend_from_slice(&decoded.s);
+        signature.push(decoded.v as u8);
+
+        Ok(Transaction {
+            from: sender,
+            to: decoded.to.unwrap_or_default(),
+            value: decoded.value,
+            gas_price: decoded.gas_price,
+            gas_limit: decoded.gas_limit,
+            gas_class: GasClass::Normal,
+            nonce: decoded.nonce,
+            data: decoded.data,
+            signature,
+            hash: tx_decode::tx_hash(raw),
+        })
     }
 }
 
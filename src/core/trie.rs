@@ -0,0 +1,167 @@
+use sha2::{Digest, Sha256};
+
+/// A sibling hash encountered while walking a Merkle proof up to the root,
+/// tagged with which side of the pairing it sits on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sibling {
+    Left([u8; 32]),
+    Right([u8; 32]),
+}
+
+/// Ordered list of sibling hashes from a leaf to the root, plus the leaf's
+/// index (needed to know left/right ordering at each level).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub leaf_index: usize,
+    pub siblings: Vec<Sibling>,
+}
+
+/// Root of an empty tree - fixed so two empty states always agree.
+pub const ZERO_ROOT: [u8; 32] = [0u8; 32];
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    let out = hasher.finalize();
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&out);
+    hash
+}
+
+pub fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Insertion-ordered binary Merkle tree built bottom-up from a fixed set of
+/// leaves. A level with an odd count duplicates its last node so every
+/// level above the leaves has an even width.
+pub struct MerkleTree {
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn from_leaves(leaves: Vec<[u8; 32]>) -> Self {
+        if leaves.is_empty() {
+            return MerkleTree { levels: vec![] };
+        }
+
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let current = levels.last().unwrap();
+            let mut next = Vec::with_capacity((current.len() + 1) / 2);
+
+            let mut i = 0;
+            while i < current.len() {
+                let left = current[i];
+                let right = if i + 1 < current.len() { current[i + 1] } else { current[i] };
+                next.push(hash_pair(&left, &right));
+                i += 2;
+            }
+
+            levels.push(next);
+        }
+
+        MerkleTree { levels }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        match self.levels.last() {
+            Some(level) => level[0],
+            None => ZERO_ROOT,
+        }
+    }
+
+    pub fn root_hex(&self) -> String {
+        hex_encode(&self.root())
+    }
+
+    /// Membership proof for the leaf at `index`, or `None` if out of range.
+    pub fn proof(&self, mut index: usize) -> Option<MerkleProof> {
+        if self.levels.is_empty() || index >= self.levels[0].len() {
+            return None;
+        }
+
+        let leaf_index = index;
+        let mut siblings = Vec::new();
+
+        for level in &self.levels[..self.levels.len() - 1] {
+            let is_right = index % 2 == 1;
+            let sibling_index = if is_right {
+                index - 1
+            } else if index + 1 < level.len() {
+                index + 1
+            } else {
+                index // odd level: last node was paired with itself
+            };
+            let sibling_hash = level[sibling_index];
+
+            siblings.push(if is_right {
+                Sibling::Left(sibling_hash)
+            } else {
+                Sibling::Right(sibling_hash)
+            });
+
+            index /= 2;
+        }
+
+        Some(MerkleProof { leaf_index, siblings })
+    }
+}
+
+/// Recompute the root implied by `leaf` and `proof`, and compare to `root`.
+pub fn verify_proof(root: &[u8; 32], leaf: &[u8; 32], proof: &MerkleProof) -> bool {
+    let mut current = *leaf;
+    for sibling in &proof.siblings {
+        current = match sibling {
+            Sibling::Left(hash) => hash_pair(hash, &current),
+            Sibling::Right(hash) => hash_pair(&current, hash),
+        };
+    }
+    &current == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_tree_has_zero_root() {
+        let tree = MerkleTree::from_leaves(vec![]);
+        assert_eq!(tree.root(), ZERO_ROOT);
+    }
+
+    #[test]
+    fn proof_verifies_for_every_leaf_odd_and_even_counts() {
+        for n in [1usize, 2, 3, 5, 8] {
+            let leaves: Vec<[u8; 32]> = (0..n)
+                .map(|i| {
+                    let mut hasher = Sha256::new();
+                    hasher.update(i.to_le_bytes());
+                    let out = hasher.finalize();
+                    let mut leaf = [0u8; 32];
+                    leaf.copy_from_slice(&out);
+                    leaf
+                })
+                .collect();
+
+            let tree = MerkleTree::from_leaves(leaves.clone());
+            let root = tree.root();
+
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = tree.proof(i).expect("proof should exist for in-range leaf");
+                assert!(verify_proof(&root, leaf, &proof), "leaf {} failed to verify (n={})", i, n);
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_leaf_fails_verification() {
+        let leaves: Vec<[u8; 32]> = vec![[1u8; 32], [2u8; 32], [3u8; 32]];
+        let tree = MerkleTree::from_leaves(leaves);
+        let root = tree.root();
+        let proof = tree.proof(1).unwrap();
+
+        assert!(!verify_proof(&root, &[9u8; 32], &proof));
+    }
+}
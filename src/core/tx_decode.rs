@@ -0,0 +1,139 @@
+// RLP decoding and ECDSA sender recovery for signed legacy Ethereum
+// transaction envelopes: rlp([nonce, gas_price, gas, to, value, data, v, r, s])
+
+use k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, VerifyingKey};
+use rlp::{Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+#[derive(Debug, Clone)]
+pub struct DecodedTransaction {
+    pub nonce: u64,
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    pub to: Option<String>,
+    pub value: u64,
+    pub data: Vec<u8>,
+    pub v: u64,
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    let out = hasher.finalize();
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&out);
+    hash
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn to_address(bytes: &[u8]) -> String {
+    format!("0x{}", hex_string(bytes))
+}
+
+fn decode_address(address: &str) -> Vec<u8> {
+    let trimmed = address.trim_start_matches("0x");
+    (0..trimmed.len())
+        .step_by(2)
+        .filter_map(|i| u8::from_str_radix(&trimmed[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Decode a raw RLP-encoded legacy transaction envelope.
+pub fn decode_raw_transaction(raw: &[u8]) -> Result<DecodedTransaction, String> {
+    let rlp = Rlp::new(raw);
+    if !rlp.is_list() || rlp.item_count().unwrap_or(0) != 9 {
+        return Err("malformed transaction: expected a 9-field RLP list".to_string());
+    }
+
+    let nonce: u64 = rlp.val_at(0).map_err(|e| format!("bad nonce: {}", e))?;
+    let gas_price: u64 = rlp.val_at(1).map_err(|e| format!("bad gas_price: {}", e))?;
+    let gas_limit: u64 = rlp.val_at(2).map_err(|e| format!("bad gas: {}", e))?;
+    let to_bytes: Vec<u8> = rlp.val_at(3).map_err(|e| format!("bad to: {}", e))?;
+    let to = if to_bytes.is_empty() { None } else { Some(to_address(&to_bytes)) };
+    let value: u64 = rlp.val_at(4).map_err(|e| format!("bad value: {}", e))?;
+    let data: Vec<u8> = rlp.val_at(5).map_err(|e| format!("bad data: {}", e))?;
+    let v: u64 = rlp.val_at(6).map_err(|e| format!("bad v: {}", e))?;
+    let r_bytes: Vec<u8> = rlp.val_at(7).map_err(|e| format!("bad r: {}", e))?;
+    let s_bytes: Vec<u8> = rlp.val_at(8).map_err(|e| format!("bad s: {}", e))?;
+
+    if r_bytes.len() > 32 || s_bytes.len() > 32 {
+        return Err("malformed signature: r/s too long".to_string());
+    }
+
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    r[32 - r_bytes.len()..].copy_from_slice(&r_bytes);
+    s[32 - s_bytes.len()..].copy_from_slice(&s_bytes);
+
+    Ok(DecodedTransaction { nonce, gas_price, gas_limit, to, value, data, v, r, s })
+}
+
+/// The hash that was actually signed. A legacy envelope (`v` of 27/28) was
+/// signed over the bare 6-field preimage with no chain id; only an
+/// EIP-155 envelope (`v >= 35`) folds `(chain_id, 0, 0)` into the preimage
+/// so the signature can't be replayed across chains. Hashing a legacy
+/// transaction as if it were EIP-155 (or vice versa) recovers the wrong
+/// sender.
+pub fn signing_hash(tx: &DecodedTransaction, chain_id: u64) -> [u8; 32] {
+    let is_eip155 = tx.v >= 35;
+
+    let mut stream = RlpStream::new();
+    stream.begin_list(if is_eip155 { 9 } else { 6 });
+    stream.append(&tx.nonce);
+    stream.append(&tx.gas_price);
+    stream.append(&tx.gas_limit);
+    match &tx.to {
+        Some(addr) => {
+            stream.append(&decode_address(addr));
+        }
+        None => {
+            stream.append_empty_data();
+        }
+    }
+    stream.append(&tx.value);
+    stream.append(&tx.data);
+    if is_eip155 {
+        stream.append(&chain_id);
+        stream.append_empty_data();
+        stream.append_empty_data();
+    }
+
+    keccak256(&stream.out())
+}
+
+/// Recover the sender's `0x…` address from a decoded transaction and the
+/// hash it signed.
+pub fn recover_sender(tx: &DecodedTransaction, signing_hash: [u8; 32]) -> Result<String, String> {
+    let recovery_byte = match tx.v {
+        27 => 0u8,
+        28 => 1u8,
+        v if v >= 35 => ((v - 35) % 2) as u8, // EIP-155: v = chain_id * 2 + 35/36
+        v => return Err(format!("unsupported recovery id v={}", v)),
+    };
+
+    let recovery_id = RecoveryId::from_byte(recovery_byte).ok_or("invalid recovery id")?;
+
+    let mut sig_bytes = [0u8; 64];
+    sig_bytes[..32].copy_from_slice(&tx.r);
+    sig_bytes[32..].copy_from_slice(&tx.s);
+    let signature = EcdsaSignature::from_slice(&sig_bytes).map_err(|e| format!("invalid signature: {}", e))?;
+
+    let verifying_key = VerifyingKey::recover_from_prehash(&signing_hash, &signature, recovery_id)
+        .map_err(|e| format!("signature recovery failed: {}", e))?;
+
+    let encoded = verifying_key.to_encoded_point(false);
+    let pubkey_bytes = &encoded.as_bytes()[1..]; // drop the 0x04 uncompressed-point prefix
+    let hash = keccak256(pubkey_bytes);
+
+    Ok(to_address(&hash[12..]))
+}
+
+/// Canonical transaction hash: keccak256 of the raw RLP bytes.
+pub fn tx_hash(raw: &[u8]) -> String {
+    format!("0x{}", hex_string(&keccak256(raw)))
+}
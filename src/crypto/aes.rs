@@ -1,33 +1,64 @@
-use sha2::{Sha256, Digest};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use sha2::Sha256;
 
-pub struct AES256 {
-    key: [u8; 32],
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Everything needed to decrypt later: the random salt the key was derived
+/// with, the random nonce the cipher ran under, and the ciphertext with the
+/// GCM authentication tag appended.
+#[derive(Debug, Clone)]
+pub struct EncryptedBlob {
+    pub salt: [u8; SALT_LEN],
+    pub nonce: [u8; NONCE_LEN],
+    pub ciphertext: Vec<u8>,
 }
 
+/// AES-256-GCM encryption under a passphrase-derived key. Stateless - the
+/// salt and nonce travel with each ciphertext instead of living on an
+/// instance, since a fresh salt is generated per encryption.
+pub struct AES256;
+
 impl AES256 {
-    pub fn new(password: &str) -> Self {
-        let mut hasher = Sha256::new();
-        hasher.update(password.as_bytes());
-        let result = hasher.finalize();
-        
+    fn derive_key(password: &str, salt: &[u8]) -> [u8; 32] {
         let mut key = [0u8; 32];
-        key.copy_from_slice(&result[..]);
-        
-        AES256 { key }
+        pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key);
+        key
     }
 
-    pub fn encrypt(&self, data: &[u8]) -> Vec<u8> {
-        // Simple XOR encryption (for demo purposes)
-        // In production, use real AES-256
-        data.iter()
-            .enumerate()
-            .map(|(i, &b)| b ^ self.key[i % 32])
-            .collect()
+    /// Encrypt `data` under `password`, generating a fresh random salt and
+    /// 96-bit nonce.
+    pub fn encrypt(password: &str, data: &[u8]) -> EncryptedBlob {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce_bytes);
+
+        let key_bytes = Self::derive_key(password, &salt);
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+        let nonce = Nonce::from_slice(&nonce_bytes);
+        let ciphertext = cipher
+            .encrypt(nonce, data)
+            .expect("AES-256-GCM encryption should never fail for in-memory buffers");
+
+        EncryptedBlob { salt, nonce: nonce_bytes, ciphertext }
     }
 
-    pub fn decrypt(&self, encrypted: &[u8]) -> Vec<u8> {
-        // XOR is symmetric
-        self.encrypt(encrypted)
+    /// Decrypt a blob produced by `encrypt`. Fails - rather than returning
+    /// garbage - if the passphrase is wrong or the ciphertext/tag has been
+    /// tampered with.
+    pub fn decrypt(password: &str, blob: &EncryptedBlob) -> Result<Vec<u8>, String> {
+        let key_bytes = Self::derive_key(password, &blob.salt);
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+        let nonce = Nonce::from_slice(&blob.nonce);
+
+        cipher
+            .decrypt(nonce, blob.ciphertext.as_ref())
+            .map_err(|_| "decryption failed: wrong passphrase or tampered ciphertext".to_string())
     }
 }
 
@@ -37,12 +68,25 @@ mod tests {
 
     #[test]
     fn test_aes256() {
-        let aes = AES256::new("super_secret_password");
         let plaintext = b"Hello, NUSA Chain!";
-        
-        let encrypted = aes.encrypt(plaintext);
-        let decrypted = aes.decrypt(&encrypted);
-        
+
+        let blob = AES256::encrypt("super_secret_password", plaintext);
+        let decrypted = AES256::decrypt("super_secret_password", &blob).unwrap();
+
         assert_eq!(plaintext, &decrypted[..]);
     }
+
+    #[test]
+    fn test_wrong_password_fails() {
+        let blob = AES256::encrypt("super_secret_password", b"Hello, NUSA Chain!");
+        assert!(AES256::decrypt("wrong_password", &blob).is_err());
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_fails() {
+        let mut blob = AES256::encrypt("super_secret_password", b"Hello, NUSA Chain!");
+        blob.ciphertext[0] ^= 0xFF;
+
+        assert!(AES256::decrypt("super_secret_password", &blob).is_err());
+    }
 }
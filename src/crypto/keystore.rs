@@ -0,0 +1,66 @@
+// Encrypted keystore for the private keys backing on-chain addresses,
+// mirroring the keyfile layout real nodes use: each key is sealed at rest
+// with AES-256-GCM under a passphrase and only touches memory in plaintext
+// once explicitly unlocked.
+
+use std::collections::HashMap;
+
+use super::aes::{EncryptedBlob, AES256};
+
+pub struct KeyStore {
+    sealed: HashMap<String, EncryptedBlob>,
+}
+
+impl KeyStore {
+    pub fn new() -> Self {
+        KeyStore { sealed: HashMap::new() }
+    }
+
+    /// Seal `private_key` for `address` under `passphrase`.
+    pub fn seal(&mut self, address: String, private_key: &[u8], passphrase: &str) {
+        let blob = AES256::encrypt(passphrase, private_key);
+        self.sealed.insert(address, blob);
+    }
+
+    /// Unlock the private key for `address`, failing if there is no
+    /// keyfile for it or the passphrase is wrong.
+    pub fn unlock(&self, address: &str, passphrase: &str) -> Result<Vec<u8>, String> {
+        let blob = self
+            .sealed
+            .get(address)
+            .ok_or_else(|| format!("no keyfile for {}", address))?;
+        AES256::decrypt(passphrase, blob)
+    }
+
+    pub fn contains(&self, address: &str) -> bool {
+        self.sealed.contains_key(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_unlock_round_trips() {
+        let mut store = KeyStore::new();
+        store.seal("0xabc".to_string(), b"deadbeefprivatekey", "hunter2");
+
+        assert!(store.contains("0xabc"));
+        assert_eq!(store.unlock("0xabc", "hunter2").unwrap(), b"deadbeefprivatekey");
+    }
+
+    #[test]
+    fn wrong_passphrase_fails() {
+        let mut store = KeyStore::new();
+        store.seal("0xabc".to_string(), b"deadbeefprivatekey", "hunter2");
+
+        assert!(store.unlock("0xabc", "wrong").is_err());
+    }
+
+    #[test]
+    fn unknown_address_fails() {
+        let store = KeyStore::new();
+        assert!(store.unlock("0xnope", "anything").is_err());
+    }
+}
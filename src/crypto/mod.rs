@@ -0,0 +1,2 @@
+pub mod aes;
+pub mod keystore;
@@ -1,11 +1,13 @@
 mod rpc;
 mod core;
 mod consensus;
+mod crypto;
 
 use std::sync::Arc;
 use tokio;
-use core::{WorldState, Mempool};
+use core::{WorldState, Mempool, BlockProvider, SqliteBlockStore, TransactionExecutor};
 use consensus::BlockProducer;
+use crypto::keystore::KeyStore;
 
 #[tokio::main]
 async fn main() {
@@ -25,27 +27,63 @@ async fn main() {
     state.create_account("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(), 1_000_000_000_000). await;
     println!("✅ Genesis accounts created");
 
+    // Seal the genesis signing keys in an encrypted keystore rather than
+    // keeping them around in plaintext. Real nodes would load this
+    // passphrase from an operator-supplied keyfile; the fallback here is
+    // for local/dev runs only.
+    let keystore_passphrase = std::env::var("NUSA_KEYSTORE_PASSPHRASE")
+        .unwrap_or_else(|_| "changeit".to_string());
+    let mut keystore = KeyStore::new();
+    keystore.seal(
+        "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+        b"genesis-signing-key-0",
+        &keystore_passphrase,
+    );
+    keystore.seal(
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+        b"genesis-signing-key-1",
+        &keystore_passphrase,
+    );
+    println!("🔒 Genesis signing keys sealed in encrypted keystore (AES-256-GCM)");
+
     // Initialize mempool
     let mempool = Arc::new(Mempool::new(10000));
     println!("✅ Mempool initialized (max: 10,000 txs)");
 
+    // Shared block store so the producer's sealed blocks are visible to
+    // RPC. Backed by sqlite so the chain tip survives a restart; point
+    // `NUSA_BLOCK_STORE_PATH` at a per-validator path to run more than
+    // one node with isolated data directories.
+    let block_store_path = std::env::var("NUSA_BLOCK_STORE_PATH")
+        .unwrap_or_else(|_| "nusa-blocks.sqlite3".to_string());
+    let block_store: Arc<dyn BlockProvider> = Arc::new(
+        SqliteBlockStore::open(&block_store_path).expect("failed to open block store"),
+    );
+
+    // Shared executor so RPC can read back receipts for blocks the
+    // producer seals (eth_getTransactionReceipt)
+    let executor = Arc::new(TransactionExecutor::new(state.clone()));
+
     // Start block producer (500ms blocks)
     let producer = Arc::new(BlockProducer::new(
         mempool.clone(),
         state.clone(),
+        executor.clone(),
+        block_store.clone(),
         "0xValidator".to_string(),
-        500, // 0.5 second block time! 
+        500, // 0.5 second block time!
+        4,   // tx pre-validation workers
     ));
-    
+
     println!("⚡ Block producer starting (0.5s block time)...");
-    
+
     let producer_clone = producer.clone();
     tokio::spawn(async move {
         producer_clone.start().await;
     });
 
     // Start RPC server
-    let server = rpc::server::RpcServer::new_with_state(state.clone(), mempool.clone());
+    let server = rpc::server::RpcServer::new_with_state(state.clone(), mempool.clone(), block_store.clone(), executor.clone(), producer.clone());
     
     println!("🚀 NUSA Chain RPC Server starting.. .");
     println!("📡 JSON-RPC: http://0.0.0.0:8545");
@@ -0,0 +1,3 @@
+pub mod server;
+pub mod scheduler;
+pub mod producer_api;
@@ -0,0 +1,89 @@
+// Bitcoin/parity-style node-state queries that don't fit the `eth_*`
+// namespace `RpcServer` already serves. Modeled on parity-zcash's
+// `build_rpc_trait!` layout: a trait describing each method, and an impl
+// holding the `Arc` handles it needs, kept separate from the ad hoc
+// `eth_*`/`nusa_*` dispatch in `server::handle_request`.
+
+use std::sync::Arc;
+use serde_json::{json, Value};
+
+use crate::consensus::BlockProducer;
+use crate::core::{BlockProvider, Mempool, Transaction};
+
+#[async_trait::async_trait]
+pub trait ProducerRpc {
+    /// Current chain height, per the block producer's own counter.
+    async fn getblocknumber(&self) -> Value;
+    async fn getblockbyhash(&self, hash: &str) -> Value;
+    async fn getblockbynumber(&self, number: u64) -> Value;
+    /// Hash of the tip of the chain this node has sealed.
+    async fn getbestblockhash(&self) -> Value;
+    /// Admits `tx` to the mempool, returning its hash on success.
+    async fn submittransaction(&self, tx: Transaction) -> Value;
+    /// Validator address, target block time, and current burn-fee /
+    /// pre-validation queue stats, for operator dashboards.
+    async fn getproducerinfo(&self) -> Value;
+}
+
+pub struct ProducerApi {
+    mempool: Arc<Mempool>,
+    blocks: Arc<dyn BlockProvider>,
+    producer: Arc<BlockProducer>,
+}
+
+impl ProducerApi {
+    pub fn new(mempool: Arc<Mempool>, blocks: Arc<dyn BlockProvider>, producer: Arc<BlockProducer>) -> Self {
+        Self { mempool, blocks, producer }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProducerRpc for ProducerApi {
+    async fn getblocknumber(&self) -> Value {
+        json!(self.producer.get_current_block_number().await)
+    }
+
+    async fn getblockbyhash(&self, hash: &str) -> Value {
+        match self.blocks.get_block_by_hash(hash) {
+            Some(block) => serde_json::to_value(block).unwrap_or(Value::Null),
+            None => json!(null),
+        }
+    }
+
+    async fn getblockbynumber(&self, number: u64) -> Value {
+        match self.blocks.get_block_by_number(number) {
+            Some(block) => serde_json::to_value(block).unwrap_or(Value::Null),
+            None => json!(null),
+        }
+    }
+
+    async fn getbestblockhash(&self) -> Value {
+        match self.blocks.latest_hash() {
+            Some(hash) => json!(hash),
+            None => json!(null),
+        }
+    }
+
+    async fn submittransaction(&self, tx: Transaction) -> Value {
+        let hash = tx.hash.clone();
+        match self.mempool.add_transaction(tx).await {
+            Ok(()) => json!(hash),
+            Err(e) => json!({"error": e}),
+        }
+    }
+
+    async fn getproducerinfo(&self) -> Value {
+        let queue = self.producer.tx_queue_info().await;
+        json!({
+            "validatorAddress": self.producer.validator_address(),
+            "blockTimeMs": self.producer.block_time_ms(),
+            "burnFee": self.producer.burnfee().await,
+            "workNeeded": self.producer.work_needed().await,
+            "txQueue": {
+                "unverified": queue.unverified_queue_size,
+                "verifying": queue.verifying_queue_size,
+                "verified": queue.verified_queue_size,
+            },
+        })
+    }
+}
@@ -0,0 +1,188 @@
+// On-demand request scheduler for light-client serving (header + state
+// proofs). These requests are cheap to forge-flood since they don't pay
+// gas, so admission is gated by a bounded priority queue plus a per-peer
+// token bucket: highest priority (and, within a priority, earliest
+// arrival) is served first, requests that sit past their deadline are
+// dropped rather than served stale, and one noisy peer can't starve
+// everyone else's tokens.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+
+/// How long a caller is willing to wait in the queue before giving up.
+pub const DEFAULT_DEADLINE: Duration = Duration::from_millis(250);
+
+/// Refill parameters for the per-peer token bucket: `capacity` tokens,
+/// refilling at `refill_per_sec` tokens/second.
+const BUCKET_CAPACITY: f64 = 20.0;
+const BUCKET_REFILL_PER_SEC: f64 = 10.0;
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new() -> Self {
+        TokenBucket { tokens: BUCKET_CAPACITY, last_refill: Instant::now() }
+    }
+
+    fn try_consume(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * BUCKET_REFILL_PER_SEC).min(BUCKET_CAPACITY);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct QueuedRequest {
+    ticket: u64,
+    priority: u8,
+    enqueued_at: Instant,
+    deadline: Instant,
+}
+
+impl PartialEq for QueuedRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.enqueued_at == other.enqueued_at
+    }
+}
+impl Eq for QueuedRequest {}
+
+impl PartialOrd for QueuedRequest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedRequest {
+    // BinaryHeap is a max-heap: higher priority sorts first, and among
+    // equal priorities the earlier arrival sorts first (so it's "greater").
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.enqueued_at.cmp(&self.enqueued_at))
+    }
+}
+
+pub enum Admission {
+    /// The request was served; its place in line.
+    Served,
+    /// Rejected before even entering the queue (peer out of tokens or the
+    /// queue is already at capacity).
+    Rejected(&'static str),
+    /// Sat in the queue past its deadline without being reached.
+    Expired,
+}
+
+struct Inner {
+    queue: BinaryHeap<QueuedRequest>,
+    buckets: HashMap<String, TokenBucket>,
+}
+
+/// Shared scheduler for `nusa_getHeaderProof` and the light handshake.
+pub struct LightRequestScheduler {
+    inner: Mutex<Inner>,
+    max_depth: usize,
+    next_ticket: AtomicU64,
+    served: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl LightRequestScheduler {
+    pub fn new(max_depth: usize) -> Arc<Self> {
+        Arc::new(LightRequestScheduler {
+            inner: Mutex::new(Inner { queue: BinaryHeap::new(), buckets: HashMap::new() }),
+            max_depth,
+            next_ticket: AtomicU64::new(0),
+            served: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Admit a request from `peer` at `priority` (higher serves first),
+    /// blocking until it reaches the front of the queue, is dropped for
+    /// exceeding `deadline`, or is rejected outright.
+    pub async fn admit(&self, peer: &str, priority: u8, deadline: Duration) -> Admission {
+        let ticket = self.next_ticket.fetch_add(1, AtomicOrdering::Relaxed);
+        let now = Instant::now();
+
+        {
+            let mut inner = self.inner.lock().await;
+
+            let bucket = inner.buckets.entry(peer.to_string()).or_insert_with(TokenBucket::new);
+            if !bucket.try_consume() {
+                return Admission::Rejected("peer token bucket exhausted");
+            }
+
+            if inner.queue.len() >= self.max_depth {
+                self.dropped.fetch_add(1, AtomicOrdering::Relaxed);
+                return Admission::Rejected("scheduler queue is at capacity");
+            }
+
+            inner.queue.push(QueuedRequest {
+                ticket,
+                priority,
+                enqueued_at: now,
+                deadline: now + deadline,
+            });
+        }
+
+        loop {
+            let mut inner = self.inner.lock().await;
+
+            // Drop anything at the front that has already expired before
+            // considering whether it's our turn.
+            while let Some(front) = inner.queue.peek() {
+                if Instant::now() >= front.deadline {
+                    inner.queue.pop();
+                    self.dropped.fetch_add(1, AtomicOrdering::Relaxed);
+                } else {
+                    break;
+                }
+            }
+
+            match inner.queue.peek() {
+                Some(front) if front.ticket == ticket => {
+                    inner.queue.pop();
+                    self.served.fetch_add(1, AtomicOrdering::Relaxed);
+                    return Admission::Served;
+                }
+                _ => {
+                    if Instant::now() >= now + deadline {
+                        inner.queue.retain(|r| r.ticket != ticket);
+                        self.dropped.fetch_add(1, AtomicOrdering::Relaxed);
+                        return Admission::Expired;
+                    }
+                }
+            }
+
+            drop(inner);
+            sleep(Duration::from_millis(2)).await;
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.inner.try_lock().map(|inner| inner.queue.len()).unwrap_or(0)
+    }
+
+    pub fn served_count(&self) -> u64 {
+        self.served.load(AtomicOrdering::Relaxed)
+    }
+
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(AtomicOrdering::Relaxed)
+    }
+}
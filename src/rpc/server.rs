@@ -1,8 +1,24 @@
 use warp::Filter;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
 use std::sync::Arc;
-use crate::core::{WorldState, Mempool};
+use tokio::sync::RwLock;
+use crate::consensus::BlockProducer;
+use crate::core::{WorldState, Mempool, BlockProvider, Transaction, TransactionExecutor};
+use crate::core::trie::{hex_encode, Sibling};
+use crate::core::snapshot::{self, Importer, ManifestBlacklist, Manifest, SnapshotChunk};
+use super::producer_api::{ProducerApi, ProducerRpc};
+use super::scheduler::{self, Admission, LightRequestScheduler};
+
+/// Chain id folded into EIP-155 signatures, matching `net_version` below.
+const CHAIN_ID: u64 = 1313376900;
+
+/// Bound on how many light requests may wait in the scheduler at once.
+const LIGHT_SCHEDULER_DEPTH: usize = 256;
+
+const HEADER_PROOF_PRIORITY: u8 = 5;
+const LIGHT_HANDSHAKE_PRIORITY: u8 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
@@ -19,27 +35,474 @@ pub struct JsonRpcResponse {
     pub id: u64,
 }
 
+/// Server-side state backing the snapshot/warp-sync RPC pair. Kept
+/// separate from `RpcServer` so it can be cheaply cloned into the warp
+/// filter closures.
+#[derive(Clone)]
+struct SnapshotState {
+    chunks: Arc<RwLock<HashMap<String, SnapshotChunk>>>,
+    import: Arc<RwLock<Option<Importer>>>,
+    blacklist: Arc<RwLock<ManifestBlacklist>>,
+}
+
+impl SnapshotState {
+    fn new() -> Self {
+        Self {
+            chunks: Arc::new(RwLock::new(HashMap::new())),
+            import: Arc::new(RwLock::new(None)),
+            blacklist: Arc::new(RwLock::new(ManifestBlacklist::new())),
+        }
+    }
+}
+
 pub struct RpcServer {
     state: Arc<WorldState>,
     mempool: Arc<Mempool>,
+    blocks: Arc<dyn BlockProvider>,
+    executor: Arc<TransactionExecutor>,
+    snapshot: SnapshotState,
+    light_scheduler: Arc<LightRequestScheduler>,
+    producer_api: Arc<ProducerApi>,
 }
 
 impl RpcServer {
-    pub fn new_with_state(state: Arc<WorldState>, mempool: Arc<Mempool>) -> Self {
-        Self { state, mempool }
+    pub fn new_with_state(
+        state: Arc<WorldState>,
+        mempool: Arc<Mempool>,
+        blocks: Arc<dyn BlockProvider>,
+        executor: Arc<TransactionExecutor>,
+        producer: Arc<BlockProducer>,
+    ) -> Self {
+        let producer_api = Arc::new(ProducerApi::new(mempool.clone(), blocks.clone(), producer));
+        Self {
+            state,
+            mempool,
+            blocks,
+            executor,
+            snapshot: SnapshotState::new(),
+            light_scheduler: LightRequestScheduler::new(LIGHT_SCHEDULER_DEPTH),
+            producer_api,
+        }
+    }
+
+    fn block_json(block: &crate::core::Block) -> serde_json::Value {
+        serde_json::to_value(block).unwrap_or(serde_json::Value::Null)
+    }
+
+    // eth_getProof-style call: returns the account plus a Merkle membership
+    // proof against the current state_root, so a light client can verify a
+    // balance without trusting the node.
+    async fn eth_get_proof(params: &serde_json::Value, state: &Arc<WorldState>) -> serde_json::Value {
+        let address = match params.get(0).and_then(|v| v.as_str()) {
+            Some(addr) => addr,
+            None => return json!({"error": "missing address parameter"}),
+        };
+
+        let root = state.state_root().await;
+
+        match state.get_proof(address).await {
+            Some((account, proof)) => {
+                let siblings: Vec<serde_json::Value> = proof.siblings.iter().map(|s| match s {
+                    Sibling::Left(hash) => json!({"side": "left", "hash": format!("0x{}", hex_encode(hash))}),
+                    Sibling::Right(hash) => json!({"side": "right", "hash": format!("0x{}", hex_encode(hash))}),
+                }).collect();
+
+                json!({
+                    "address": account.address,
+                    "balance": format!("0x{:x}", account.balance),
+                    "nonce": format!("0x{:x}", account.nonce),
+                    "stateRoot": format!("0x{}", root),
+                    "proof": {
+                        "leafIndex": proof.leaf_index,
+                        "siblings": siblings,
+                    },
+                })
+            }
+            None => json!({"error": "account not found"}),
+        }
+    }
+
+    // Serving side of warp-sync: partitions the current state into chunks,
+    // caches them by hash so a later nusa_restoreChunk round can fetch
+    // them, and returns the manifest describing the snapshot.
+    async fn nusa_snapshot_manifest(
+        params: &serde_json::Value,
+        state: &Arc<WorldState>,
+        snapshot: &SnapshotState,
+    ) -> serde_json::Value {
+        let chunk_size = params
+            .get(0)
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize)
+            .unwrap_or(snapshot::DEFAULT_CHUNK_SIZE);
+
+        let (manifest, chunks) = snapshot::export(state, 0, "0x0".to_string(), chunk_size).await;
+
+        let mut cache = snapshot.chunks.write().await;
+        for chunk in &chunks {
+            cache.insert(chunk.hash(), chunk.clone());
+        }
+        drop(cache);
+
+        json!({
+            "manifest": manifest,
+            "chunks": chunks,
+        })
     }
 
-    async fn handle_request(req: JsonRpcRequest, state: Arc<WorldState>) -> JsonRpcResponse {
+    // Import side of warp-sync: verifies a single chunk against the
+    // manifest's outstanding set, applies it, and finalizes (checking the
+    // restored root) once every chunk has landed. A manifest that fails
+    // finalization is blacklisted so it is never retried.
+    async fn nusa_restore_chunk(
+        params: &serde_json::Value,
+        state: &Arc<WorldState>,
+        snapshot: &SnapshotState,
+    ) -> serde_json::Value {
+        let manifest: Manifest = match params.get(0).cloned().and_then(|v| serde_json::from_value(v).ok()) {
+            Some(m) => m,
+            None => return json!({"error": "missing or invalid manifest parameter"}),
+        };
+        let chunk: SnapshotChunk = match params.get(1).cloned().and_then(|v| serde_json::from_value(v).ok()) {
+            Some(c) => c,
+            None => return json!({"error": "missing or invalid chunk parameter"}),
+        };
+
+        if snapshot.blacklist.read().await.is_blacklisted(&manifest) {
+            return json!({"error": "manifest is blacklisted"});
+        }
+
+        let mut import = snapshot.import.write().await;
+        if import.is_none() {
+            *import = Some(Importer::new(manifest.clone()));
+        }
+        let importer = import.as_mut().unwrap();
+
+        if let Err(e) = importer.apply_chunk(state, chunk).await {
+            return json!({"error": e});
+        }
+
+        if !importer.is_complete() {
+            let remaining = importer.remaining();
+            return json!({"status": "pending", "remaining": remaining});
+        }
+
+        let finalize_result = importer.finalize(state).await;
+        *import = None;
+
+        match finalize_result {
+            Ok(()) => json!({"status": "restored", "stateRoot": state.state_root().await}),
+            Err(e) => {
+                snapshot.blacklist.write().await.blacklist(&manifest);
+                json!({"error": format!("restore failed, manifest blacklisted: {}", e)})
+            }
+        }
+    }
+
+    // Decodes a raw RLP transaction, recovers its sender via ECDSA, checks
+    // the recovered nonce against the sender's account, and - on success -
+    // admits it to the mempool under its real keccak256 hash.
+    async fn eth_send_raw_transaction(
+        params: &serde_json::Value,
+        state: &Arc<WorldState>,
+        mempool: &Arc<Mempool>,
+    ) -> serde_json::Value {
+        let raw_hex = match params.get(0).and_then(|v| v.as_str()) {
+            Some(s) => s.trim_start_matches("0x"),
+            None => return json!({"error": "missing raw transaction parameter"}),
+        };
+
+        let raw = match (0..raw_hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&raw_hex[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+        {
+            Ok(bytes) => bytes,
+            Err(_) => return json!({"error": "raw transaction is not valid hex"}),
+        };
+
+        let tx = match Transaction::from_raw(&raw, CHAIN_ID) {
+            Ok(tx) => tx,
+            Err(e) => return json!({"error": e}),
+        };
+
+        let expected_nonce = state.get_nonce(&tx.from).await;
+        if tx.nonce != expected_nonce {
+            return json!({"error": format!(
+                "nonce too {}: tx has {}, account is at {}",
+                if tx.nonce < expected_nonce { "low" } else { "high" },
+                tx.nonce,
+                expected_nonce
+            )});
+        }
+
+        let hash = tx.hash.clone();
+        match mempool.add_transaction(tx).await {
+            Ok(()) => json!(hash),
+            Err(e) => json!({"error": e}),
+        }
+    }
+
+    async fn eth_get_balance(params: &serde_json::Value, state: &Arc<WorldState>) -> serde_json::Value {
+        let address = match params.get(0).and_then(|v| v.as_str()) {
+            Some(a) => a,
+            None => return json!({"error": "missing address parameter"}),
+        };
+
+        json!(format!("0x{:x}", state.get_balance(address).await.unwrap_or(0)))
+    }
+
+    async fn eth_get_transaction_count(params: &serde_json::Value, state: &Arc<WorldState>) -> serde_json::Value {
+        let address = match params.get(0).and_then(|v| v.as_str()) {
+            Some(a) => a,
+            None => return json!({"error": "missing address parameter"}),
+        };
+
+        json!(format!("0x{:x}", state.get_nonce(address).await))
+    }
+
+    // Read-only call: this does not run contract bytecode; it only
+    // validates the same thing `eth_sendRawTransaction` would actually move
+    // - that `from` can cover `value` - without touching state.
+    async fn eth_call(params: &serde_json::Value, state: &Arc<WorldState>) -> serde_json::Value {
+        let call = match params.get(0) {
+            Some(c) => c,
+            None => return json!({"error": "missing call object parameter"}),
+        };
+
+        let from = call.get("from").and_then(|v| v.as_str()).unwrap_or("");
+        let value = call
+            .get("value")
+            .and_then(|v| v.as_str())
+            .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+            .unwrap_or(0);
+
+        if value == 0 {
+            return json!("0x");
+        }
+
+        match state.get_balance(from).await {
+            Some(balance) if balance >= value => json!("0x01"),
+            _ => json!({"error": "insufficient balance for call"}),
+        }
+    }
+
+    async fn eth_get_transaction_receipt(
+        params: &serde_json::Value,
+        executor: &Arc<TransactionExecutor>,
+    ) -> serde_json::Value {
+        let tx_hash = match params.get(0).and_then(|v| v.as_str()) {
+            Some(h) => h,
+            None => return json!({"error": "missing transaction hash parameter"}),
+        };
+
+        match executor.get_receipt(tx_hash).await {
+            Some(receipt) => serde_json::to_value(receipt).unwrap_or(serde_json::Value::Null),
+            None => json!(null),
+        }
+    }
+
+    // Light-client proof serving: a `BlockHeader` plus the account's Merkle
+    // proof against that header's state_root. Only the latest block can be
+    // served this way - this node keeps just the live trie, not historical
+    // ones, so the header's state_root is only guaranteed to match a proof
+    // freshly built from `WorldState` for the most recently sealed block.
+    async fn nusa_get_header_proof(
+        params: &serde_json::Value,
+        state: &Arc<WorldState>,
+        blocks: &Arc<dyn BlockProvider>,
+        light_scheduler: &Arc<LightRequestScheduler>,
+        peer: &str,
+    ) -> serde_json::Value {
+        match light_scheduler.admit(peer, HEADER_PROOF_PRIORITY, scheduler::DEFAULT_DEADLINE).await {
+            Admission::Served => {}
+            Admission::Rejected(reason) => return json!({"error": format!("request rejected: {}", reason)}),
+            Admission::Expired => return json!({"error": "request expired waiting for the scheduler"}),
+        }
+
+        let address = match params.get(0).and_then(|v| v.as_str()) {
+            Some(a) => a,
+            None => return json!({"error": "missing address parameter"}),
+        };
+
+        let latest_hash = match blocks.latest_hash() {
+            Some(h) => h,
+            None => return json!({"error": "no blocks produced yet"}),
+        };
+        let header = match blocks.block_header(&latest_hash) {
+            Some(h) => h,
+            None => return json!({"error": "latest block header not found"}),
+        };
+
+        match state.get_proof(address).await {
+            Some((account, proof)) => {
+                let siblings: Vec<serde_json::Value> = proof.siblings.iter().map(|s| match s {
+                    Sibling::Left(hash) => json!({"side": "left", "hash": format!("0x{}", hex_encode(hash))}),
+                    Sibling::Right(hash) => json!({"side": "right", "hash": format!("0x{}", hex_encode(hash))}),
+                }).collect();
+
+                json!({
+                    "header": header,
+                    "address": account.address,
+                    "balance": format!("0x{:x}", account.balance),
+                    "nonce": format!("0x{:x}", account.nonce),
+                    "proof": {
+                        "leafIndex": proof.leaf_index,
+                        "siblings": siblings,
+                    },
+                })
+            }
+            None => json!({"error": "account not found"}),
+        }
+    }
+
+    // Advertises that this node serves light-client proofs, so a peer can
+    // decide whether to warp-sync from it before spending a request budget.
+    async fn nusa_light_handshake(
+        blocks: &Arc<dyn BlockProvider>,
+        light_scheduler: &Arc<LightRequestScheduler>,
+        peer: &str,
+    ) -> serde_json::Value {
+        match light_scheduler.admit(peer, LIGHT_HANDSHAKE_PRIORITY, scheduler::DEFAULT_DEADLINE).await {
+            Admission::Served => {}
+            Admission::Rejected(reason) => return json!({"error": format!("request rejected: {}", reason)}),
+            Admission::Expired => return json!({"error": "request expired waiting for the scheduler"}),
+        }
+
+        json!({
+            "servesHeaderProofs": true,
+            "servesSnapshots": true,
+            "latestBlock": blocks.latest_number(),
+        })
+    }
+
+    async fn handle_request(
+        req: JsonRpcRequest,
+        state: Arc<WorldState>,
+        mempool: Arc<Mempool>,
+        blocks: Arc<dyn BlockProvider>,
+        executor: Arc<TransactionExecutor>,
+        snapshot: SnapshotState,
+        light_scheduler: Arc<LightRequestScheduler>,
+        producer_api: Arc<ProducerApi>,
+        peer: String,
+    ) -> JsonRpcResponse {
+        if req.method == "getblocknumber" {
+            let result = producer_api.getblocknumber().await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "getblockbyhash" {
+            let result = match req.params.get(0).and_then(|v| v.as_str()) {
+                Some(hash) => producer_api.getblockbyhash(hash).await,
+                None => json!({"error": "missing hash parameter"}),
+            };
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "getblockbynumber" {
+            let result = match req.params.get(0).and_then(|v| v.as_u64()) {
+                Some(number) => producer_api.getblockbynumber(number).await,
+                None => json!({"error": "missing number parameter"}),
+            };
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "getbestblockhash" {
+            let result = producer_api.getbestblockhash().await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "submittransaction" {
+            let result = match req.params.get(0).cloned().and_then(|v| serde_json::from_value(v).ok()) {
+                Some(tx) => producer_api.submittransaction(tx).await,
+                None => json!({"error": "missing or invalid transaction parameter"}),
+            };
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "getproducerinfo" {
+            let result = producer_api.getproducerinfo().await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "eth_getProof" {
+            let result = Self::eth_get_proof(&req.params, &state).await;
+            return JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                result,
+                id: req.id,
+            };
+        }
+
+        if req.method == "nusa_snapshotManifest" {
+            let result = Self::nusa_snapshot_manifest(&req.params, &state, &snapshot).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "nusa_restoreChunk" {
+            let result = Self::nusa_restore_chunk(&req.params, &state, &snapshot).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "eth_sendRawTransaction" {
+            let result = Self::eth_send_raw_transaction(&req.params, &state, &mempool).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "eth_getBalance" {
+            let result = Self::eth_get_balance(&req.params, &state).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "eth_getTransactionCount" {
+            let result = Self::eth_get_transaction_count(&req.params, &state).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "eth_call" {
+            let result = Self::eth_call(&req.params, &state).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "eth_getTransactionReceipt" {
+            let result = Self::eth_get_transaction_receipt(&req.params, &executor).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "nusa_getHeaderProof" {
+            let result = Self::nusa_get_header_proof(&req.params, &state, &blocks, &light_scheduler, &peer).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
+        if req.method == "nusa_lightHandshake" {
+            let result = Self::nusa_light_handshake(&blocks, &light_scheduler, &peer).await;
+            return JsonRpcResponse { jsonrpc: "2.0".to_string(), result, id: req.id };
+        }
+
         let result = match req.method.as_str() {
-            "eth_blockNumber" => json!("0x1"),
+            "eth_blockNumber" => json!(format!("0x{:x}", blocks.latest_number())),
             "eth_chainId" => json! ("0x4e555341"),
             "net_version" => json!("1313376900"),
             "eth_accounts" => json!(["0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb"]),
             "eth_gasPrice" => json!("0x3b9aca00"),
-            "eth_getBalance" => json! ("0xde0b6b3a7640000"),
-            "eth_getBlockByNumber" => json!({"number": "0x1", "hash": "0xabc123"}),
+            "eth_getBlockByNumber" => {
+                let number = req.params.get(0)
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok());
+                match number.and_then(|n| blocks.block_hash(n)).and_then(|h| blocks.block(&h)) {
+                    Some(block) => Self::block_json(&block),
+                    None => json!(null),
+                }
+            }
+            "eth_getBlockByHash" => {
+                let hash = req.params.get(0).and_then(|v| v.as_str());
+                match hash.and_then(|h| blocks.block(h)) {
+                    Some(block) => Self::block_json(&block),
+                    None => json!(null),
+                }
+            }
             "eth_sendTransaction" => json! ("0xtxhash123"),
-            "eth_call" => json!("0x01"),
             "eth_estimateGas" => json!("0x5208"),
             
             "nusa_posInfo" => json!({"consensus": "PoS", "validators": 21, "status": "operational"}),
@@ -92,14 +555,30 @@ impl RpcServer {
 
     pub async fn run(self) {
         let state = self.  state.clone();
+        let mempool = self.mempool.clone();
+        let blocks = self.blocks.clone();
+        let executor = self.executor.clone();
+        let snapshot = self.snapshot.clone();
+        let light_scheduler = self.light_scheduler.clone();
+        let producer_api = self.producer_api.clone();
 
         let rpc = warp::post()
             .and(warp::path::end())
             .and(warp::body::json())
-            .and_then(move |req: JsonRpcRequest| {
+            .and(warp::addr::remote())
+            .and_then(move |req: JsonRpcRequest, remote: Option<std::net::SocketAddr>| {
                 let state = state. clone();
+                let mempool = mempool.clone();
+                let blocks = blocks.clone();
+                let executor = executor.clone();
+                let snapshot = snapshot.clone();
+                let light_scheduler = light_scheduler.clone();
+                let producer_api = producer_api.clone();
                 async move {
-                    let response = Self::handle_request(req, state). await;
+                    let peer = remote.map(|addr| addr.to_string()).unwrap_or_else(|| "unknown".to_string());
+                    let response = Self::handle_request(
+                        req, state, mempool, blocks, executor, snapshot, light_scheduler, producer_api, peer,
+                    ). await;
                     Ok::<_, warp::Rejection>(warp::reply::json(&response))
                 }
             });
@@ -107,9 +586,12 @@ impl RpcServer {
         let health = warp::path("health")
             .map(|| warp::reply::json(&json!({"status": "healthy"})));
 
+        let metrics_scheduler = self.light_scheduler.clone();
+        let metrics_mempool = self.mempool.clone();
         let metrics = warp::path("metrics")
-            .map(|| {
-                let metrics_data = r#"
+            .map(move || {
+                let metrics_data = format!(
+                    r#"
 # HELP nusa_block_height Current block height
 # TYPE nusa_block_height gauge
 nusa_block_height 12345
@@ -117,7 +599,28 @@ nusa_block_height 12345
 # HELP nusa_tps Transactions per second
 # TYPE nusa_tps gauge
 nusa_tps 50000
-"#;
+
+# HELP nusa_light_scheduler_depth Requests currently queued for light serving
+# TYPE nusa_light_scheduler_depth gauge
+nusa_light_scheduler_depth {depth}
+
+# HELP nusa_light_scheduler_served_total Light requests served so far
+# TYPE nusa_light_scheduler_served_total counter
+nusa_light_scheduler_served_total {served}
+
+# HELP nusa_light_scheduler_dropped_total Light requests dropped (deadline or capacity)
+# TYPE nusa_light_scheduler_dropped_total counter
+nusa_light_scheduler_dropped_total {dropped}
+
+# HELP nusa_mempool_dedup_check_time_ns Time the last add_transaction call spent checking the processed-status cache
+# TYPE nusa_mempool_dedup_check_time_ns gauge
+nusa_mempool_dedup_check_time_ns {check_time_ns}
+"#,
+                    depth = metrics_scheduler.depth(),
+                    served = metrics_scheduler.served_count(),
+                    dropped = metrics_scheduler.dropped_count(),
+                    check_time_ns = metrics_mempool.check_time_ns(),
+                );
                 warp::reply::with_header(metrics_data, "Content-Type", "text/plain")
             });
 